@@ -23,7 +23,9 @@ fn main() {
     // ext.intercept(on_save_trigger);
     // ext.intercept(on_save_addon);
     thread::spawn(move || {
-        ext.run();
+        if let Err(e) = ext.run() {
+            eprintln!("{e}");
+        }
     });
 }
 
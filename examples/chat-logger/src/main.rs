@@ -10,14 +10,14 @@ struct ChatLogger {
     user_name_by_index: HashMap<i32, String>
 }
 
-fn main() {
+fn main() -> g_rust::Result<()> {
     let mut chat_logger: Extension<ChatLogger> = Extension::new();
     chat_logger.info.name = String::from("Chat Logger");
     chat_logger.intercept(on_users);
     chat_logger.intercept(on_close_connection);
     chat_logger.intercept(on_user_remove);
     chat_logger.intercept(on_chat);
-    chat_logger.run();
+    chat_logger.run()
 }
 
 fn on_users(ext: &mut Extension<ChatLogger>, _: &mut HMessage, users: &mut Users) {
@@ -1,6 +1,5 @@
 use g_rust::extension::extension::{Extension, ExtensionInfo};
 use g_rust::extension::parsers::incoming::{CloseConnection, WiredFurniAction, WiredFurniAddon, WiredFurniCondition, WiredFurniSelector, WiredFurniTrigger, WiredSaveSuccess};
-use g_rust::extension::parsers::outgoing::Chat;
 use g_rust::extension::parsers::subparsers::SelectorDefinition;
 use g_rust::protocol::hmessage::HMessage;
 
@@ -23,7 +22,7 @@ impl ClearOpenWired {
     }
 }
 
-fn main() {
+fn main() -> g_rust::Result<()> {
     let mut ext: Extension<ClearOpenWired> = Extension::new();
     ext.info = ExtensionInfo {
         name: "Clear Wired Selection".to_string(),
@@ -31,42 +30,51 @@ fn main() {
         author: "WiredSpast".to_string(),
         version: "0.1.0".to_string(),
     };
-    ext.intercept(on_chat);
+    ext.command(":cw", clear_selection)
+        .description("Clears the selected furni in the currently open wired");
     ext.intercept(on_save_success);
     ext.intercept(on_selector_def);
     ext.intercept(on_condition_def);
     ext.intercept(on_action_def);
     ext.intercept(on_trigger_def);
     ext.intercept(on_addon_def);
-    ext.run();
+    ext.run()
 }
 
-fn on_chat(ext: &mut Extension<ClearOpenWired>, _msg: &mut HMessage, chat: &mut Chat) {
-    if chat.text == ":cw" {
-        if ext.globals.last_opened_selector.is_some() {
-            let mut selector = ext.globals.last_opened_selector.clone().unwrap();
-            selector.def.stuff_ids = vec![];
-            ext.send(selector);
+fn clear_selection(ext: &mut Extension<ClearOpenWired>, _: ()) {
+    if ext.globals.last_opened_selector.is_some() {
+        let mut selector = ext.globals.last_opened_selector.clone().unwrap();
+        selector.def.stuff_ids = vec![];
+        if let Err(e) = ext.send(selector) {
+            eprintln!("Couldn't clear selector: {e}");
         }
-        if ext.globals.last_opened_condition.is_some() {
-            let mut condition = ext.globals.last_opened_condition.clone().unwrap();
-            condition.def.stuff_ids = vec![];
-            ext.send(condition);
+    }
+    if ext.globals.last_opened_condition.is_some() {
+        let mut condition = ext.globals.last_opened_condition.clone().unwrap();
+        condition.def.stuff_ids = vec![];
+        if let Err(e) = ext.send(condition) {
+            eprintln!("Couldn't clear condition: {e}");
         }
-        if ext.globals.last_opened_action.is_some() {
-            let mut action = ext.globals.last_opened_action.clone().unwrap();
-            action.def.stuff_ids = vec![];
-            ext.send(action);
+    }
+    if ext.globals.last_opened_action.is_some() {
+        let mut action = ext.globals.last_opened_action.clone().unwrap();
+        action.def.stuff_ids = vec![];
+        if let Err(e) = ext.send(action) {
+            eprintln!("Couldn't clear action: {e}");
         }
-        if ext.globals.last_opened_trigger.is_some() {
-            let mut trigger = ext.globals.last_opened_trigger.clone().unwrap();
-            trigger.def.stuff_ids = vec![];
-            ext.send(trigger);
+    }
+    if ext.globals.last_opened_trigger.is_some() {
+        let mut trigger = ext.globals.last_opened_trigger.clone().unwrap();
+        trigger.def.stuff_ids = vec![];
+        if let Err(e) = ext.send(trigger) {
+            eprintln!("Couldn't clear trigger: {e}");
         }
-        if ext.globals.last_opened_addon.is_some() {
-            let mut addon = ext.globals.last_opened_addon.clone().unwrap();
-            addon.def.stuff_ids = vec![];
-            ext.send(addon);
+    }
+    if ext.globals.last_opened_addon.is_some() {
+        let mut addon = ext.globals.last_opened_addon.clone().unwrap();
+        addon.def.stuff_ids = vec![];
+        if let Err(e) = ext.send(addon) {
+            eprintln!("Couldn't clear addon: {e}");
         }
     }
 }
@@ -30,28 +30,33 @@ fn impl_packet_variable(ast: &syn::DeriveInput) -> Tokens {
 
 fn impl_struct_derive(name: &Ident, fields: &Vec<Field>) -> Tokens {
     let from_idents = fields.iter().map(| f | &f.ident);
+    let field_idents = from_idents.clone();
     let to_idents = from_idents.clone();
 
     quote! {
         impl PacketVariable for #name {
-            fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-                let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-                (
+            fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+                crate::protocol::vars::packetvariable::expect_read(Self::try_from_packet(bytes))
+            }
+
+            fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), crate::error::DecodeError> where Self: Sized {
+                let mut reader = crate::protocol::packetreader::PacketReader::new(bytes);
+                Ok((
                     Self {
                         #(
-                            #from_idents: packet.read()
+                            #from_idents: reader.try_read_field(stringify!(#field_idents))?
                         ),*
                     },
-                    packet.read_index - 6
-                )
+                    reader.position()
+                ))
             }
 
             fn to_packet(&self) -> Vec<u8> {
-                let mut packet = HPacket::from_header_id(0);
+                let mut bytes = Vec::new();
                 #(
-                    packet.append(self.#to_idents.clone());
+                    bytes.extend(self.#to_idents.to_packet());
                 )*
-                packet.get_bytes()[6..].to_vec()
+                bytes
             }
         }
     }
@@ -1,12 +1,14 @@
 use darling::FromDeriveInput;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{ parse_macro_input, DeriveInput };
+use syn::{ parse_macro_input, Data, DeriveInput, Fields };
 
 #[derive(FromDeriveInput, Default)]
 #[darling(default, attributes(to))]
 struct Opts {
-    direction: Option<u8>
+    direction: Option<u8>,
+    /// The packet has a hand-written `PacketVariable` impl, parse it through that instead of field by field
+    manual: bool
 }
 
 #[proc_macro_derive(BaseParser, attributes(to))]
@@ -17,10 +19,33 @@ pub fn base_parser_derive(input: TokenStream) -> TokenStream {
     let name = input.ident;
     let name_string = name.to_string();
 
+    let try_parse = match &input.data {
+        Data::Struct(data) if !opts.manual => match &data.fields {
+            Fields::Named(fields) => {
+                let idents = fields.named.iter().map(| f | &f.ident);
+                let field_idents = idents.clone();
+                quote! {
+                    Ok(Self {
+                        #(
+                            #idents: packet.try_read_field(stringify!(#field_idents))?
+                        ),*
+                    })
+                }
+            },
+            Fields::Unit => quote! { Ok(Self) },
+            Fields::Unnamed(_) => quote! { packet.try_read() }
+        },
+        _ => quote! { packet.try_read() }
+    };
+
     let expanded = quote! {
         impl BaseParser for #name {
             fn parse(packet: &mut HPacket) -> Self {
-                packet.read()
+                crate::protocol::vars::packetvariable::expect_read(Self::try_parse(packet))
+            }
+
+            fn try_parse(packet: &mut HPacket) -> Result<Self, crate::error::DecodeError> {
+                #try_parse
             }
 
             fn append_to_packet(&self, packet: &mut HPacket) {
@@ -38,4 +63,4 @@ pub fn base_parser_derive(input: TokenStream) -> TokenStream {
     };
 
     TokenStream::from(expanded)
-}
\ No newline at end of file
+}
@@ -0,0 +1,142 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use crate::protocol::hdirection::HDirection;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while talking to G-Earth or reading and writing packets
+#[derive(Debug)]
+pub enum Error {
+    /// Couldn't connect to, read from or write to G-Earth
+    Connection(io::Error),
+    /// Not connected to G-Earth (yet), or the connection was lost
+    Disconnected,
+    /// G-Earth sent a message with an invalid length
+    Framing(i32),
+    /// The packet info manager doesn't know the packet
+    UnknownPacket { direction: HDirection, name: String },
+    /// A value couldn't be read from a packet
+    Decode(DecodeError),
+    /// A packet couldn't be encoded for sending
+    Encoding(String),
+    /// No matching response arrived in time
+    Timeout,
+    /// A queued packet was cancelled before it was sent
+    Cancelled,
+    /// The command-line arguments or their environment variables are invalid
+    Arguments(String),
+    /// The extension's saved state couldn't be read or written
+    State(String),
+    /// A capture file couldn't be read or written
+    Capture(String),
+    /// A packet log couldn't be read or parsed
+    PacketLog(String)
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Connection(e) => write!(f, "connection with G-Earth failed: {e}"),
+            Error::Disconnected => write!(f, "not connected to G-Earth"),
+            Error::Framing(length) => write!(f, "invalid message length {length}"),
+            Error::UnknownPacket { direction, name } => write!(f, "unknown packet {name} ({direction:?})"),
+            Error::Decode(e) => write!(f, "couldn't read packet: {e}"),
+            Error::Encoding(reason) => write!(f, "couldn't encode packet: {reason}"),
+            Error::Timeout => write!(f, "timed out waiting for response"),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::Arguments(reason) => write!(f, "invalid arguments: {reason}"),
+            Error::State(reason) => write!(f, "couldn't persist state: {reason}"),
+            Error::Capture(reason) => write!(f, "invalid capture: {reason}"),
+            Error::PacketLog(reason) => write!(f, "invalid packet log: {reason}")
+        }
+    }
+}
+
+impl Error {
+    /// Whether the connection with G-Earth failed or was lost, rather than the extension being misconfigured
+    pub fn is_connection_error(&self) -> bool {
+        matches!(self, Error::Connection(_) | Error::Disconnected)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Connection(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => Error::Disconnected,
+            _ => Error::Connection(e)
+        }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Decode(e)
+    }
+}
+
+/// Why and where reading a value from a packet failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Fields leading to the value that couldn't be read, outermost first
+    pub path: Vec<String>,
+    /// Byte offset of the value that couldn't be read, relative to the packet body
+    /// (or to the bytes passed to `try_from_packet`)
+    pub offset: usize,
+    pub reason: String
+}
+
+impl DecodeError {
+    pub fn new(offset: usize, reason: impl Into<String>) -> Self {
+        Self {
+            path: Vec::new(),
+            offset,
+            reason: reason.into()
+        }
+    }
+
+    /// Marks the error as having happened inside `field`
+    pub fn in_field(mut self, field: impl Into<String>) -> Self {
+        self.path.insert(0, field.into());
+        self
+    }
+
+    /// Moves the offset for a value that started `offset` bytes further
+    pub fn shifted(mut self, offset: usize) -> Self {
+        self.offset += offset;
+        self
+    }
+
+    /// The path as it would be written in Rust, e.g. `users[2].name`
+    pub fn field_path(&self) -> String {
+        let mut res = String::new();
+        for field in self.path.iter() {
+            if !res.is_empty() && !field.starts_with('[') {
+                res.push('.');
+            }
+            res.push_str(field);
+        }
+        res
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "at byte {}: {}", self.offset, self.reason)
+        } else {
+            write!(f, "{} at byte {}: {}", self.field_path(), self.offset, self.reason)
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use crate::error::{Error, Result};
+
+/// Port G-Earth listens on for extensions unless configured otherwise
+pub const DEFAULT_PORT: u16 = 9092;
+
+/// Environment variable used for the port when `--port` isn't given
+pub const PORT_ENV: &str = "G_EARTH_PORT";
+/// Environment variable used for the file name when `--filename` isn't given
+pub const FILE_ENV: &str = "G_EARTH_FILENAME";
+/// Environment variable used for the auth token when `--auth-token` isn't given
+pub const COOKIE_ENV: &str = "G_EARTH_AUTH_TOKEN";
+
+pub const PORT_ARGUMENT: &str = "--port";
+pub const FILE_ARGUMENT: &str = "--filename";
+pub const COOKIE_ARGUMENT: &str = "--auth-token";
+
+/// A command-line argument taking a value, like `--port 9092` or `--port=9092`
+///
+/// Given without a value, like `--verbose` at the end or followed by another `-` argument, it's a flag with the value `true`.
+/// Values starting with `-` have to be given as `--room=-5`. Names are matched case-insensitively
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Argument {
+    names: Vec<String>,
+    env: Option<String>,
+    required: bool
+}
+
+impl Argument {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            names: vec![name.into()],
+            env: None,
+            required: false
+        }
+    }
+
+    /// Adds another name, e.g. a short `-r` for `--room`
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.names.push(alias.into());
+        self
+    }
+
+    /// Environment variable read when the argument isn't given on the command line
+    pub fn env(mut self, var: impl Into<String>) -> Self {
+        self.env = Some(var.into());
+        self
+    }
+
+    /// Makes parsing fail when the argument is missing
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.names[0]
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.names.iter().any(| n | n.eq_ignore_ascii_case(name))
+    }
+
+    /// The arguments G-Earth starts extensions with
+    pub fn g_earth() -> Vec<Argument> {
+        vec![
+            Argument::new(PORT_ARGUMENT).alias("-p").env(PORT_ENV),
+            Argument::new(FILE_ARGUMENT).alias("-f").env(FILE_ENV),
+            Argument::new(COOKIE_ARGUMENT).alias("-c").env(COOKIE_ENV)
+        ]
+    }
+}
+
+/// Values of the declared [`Argument`]s, from the command line or their environment variables
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Arguments {
+    values: HashMap<String, String>
+}
+
+impl Arguments {
+    /// Parses `args` (the program name and anything not starting with `-` are skipped) for G-Earth's arguments and `arguments`
+    ///
+    /// Unknown arguments are ignored, missing required ones are an error
+    pub fn parse(args: &[String], arguments: &[Argument]) -> Result<Self> {
+        Self::parse_with_env(args, arguments, | var | env::var(var).ok())
+    }
+
+    /// Same as [`parse`](Self::parse), looking up environment variables with `env` instead of the process environment
+    pub fn parse_with_env(args: &[String], arguments: &[Argument], env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let all: Vec<Argument> = Argument::g_earth().into_iter().chain(arguments.iter().cloned()).collect();
+        let mut values = HashMap::new();
+
+        let mut i = 0;
+        while i < args.len() {
+            let (name, inline_value) = match args[i].split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (args[i].as_str(), None)
+            };
+            i += 1;
+            if !name.starts_with('-') {
+                continue;
+            }
+            let Some(argument) = all.iter().find(| argument | argument.matches(name)) else {
+                continue;
+            };
+
+            let value = match inline_value {
+                Some(value) => value,
+                None if i < args.len() && !args[i].starts_with('-') => {
+                    i += 1;
+                    args[i - 1].clone()
+                },
+                None => String::from("true")
+            };
+            values.insert(argument.name().to_string(), value);
+        }
+
+        for argument in all.iter() {
+            if values.contains_key(argument.name()) {
+                continue;
+            }
+            if let Some(value) = argument.env.as_ref().and_then(| var | env(var)) {
+                values.insert(argument.name().to_string(), value);
+            } else if argument.required {
+                let env = argument.env.as_ref().map(| var | format!(" (or set {var})")).unwrap_or_default();
+                return Err(Error::Arguments(format!("missing required argument {}{env}", argument.name())));
+            }
+        }
+
+        let res = Self { values };
+        res.port()?;
+        Ok(res)
+    }
+
+    /// Raw value of the argument called `name`, by its first name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(| value | value.as_str())
+    }
+
+    /// Value of the argument called `name` as a `T`, fails if it isn't a valid `T`
+    pub fn get_parsed<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.get(name)
+            .map(| value | value.parse().map_err(| _ | Error::Arguments(format!(
+                "\"{value}\" is not a valid {} for {name}", std::any::type_name::<T>().rsplit("::").next().unwrap_or_default()
+            ))))
+            .transpose()
+    }
+
+    /// Port to connect to G-Earth on, [`DEFAULT_PORT`] if not given
+    pub fn port(&self) -> Result<u16> {
+        Ok(self.get_parsed(PORT_ARGUMENT)?.unwrap_or(DEFAULT_PORT))
+    }
+
+    /// File G-Earth started the extension from, if any
+    pub fn file(&self) -> Option<&str> {
+        self.get(FILE_ARGUMENT)
+    }
+
+    /// Token G-Earth authenticates the extension with, if any
+    pub fn cookie(&self) -> Option<&str> {
+        self.get(COOKIE_ARGUMENT)
+    }
+}
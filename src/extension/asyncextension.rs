@@ -0,0 +1,595 @@
+use std::env;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use super::arguments::{Argument, Arguments};
+use super::extension::{Capabilities, ExtensionInfo, InterceptIndicator, PortSource};
+use super::extensionhandle::{resolve_header_id, wrap_send_packet};
+use super::intercept::{InterceptHandle, InterceptRegistry, InterceptTarget, DEFAULT_PRIORITY};
+use super::parsers::baseparser::BaseParser;
+use super::reconnect::ReconnectPolicy;
+use crate::error::{Error, Result};
+use crate::misc::asyncconnection::{AsyncGEarthConnection, AsyncGEarthWriter};
+use crate::misc::connectioninfo::ConnectionInfo;
+use crate::misc::consoleformat::ConsoleColour;
+use crate::misc::hclient::CUR_CLIENT;
+use crate::misc::hostinfo::HostInfo;
+use crate::misc::messages::*;
+use crate::protocol::hdirection::HDirection;
+use crate::protocol::hmessage::HMessage;
+use crate::protocol::hpacket::HPacket;
+use crate::protocol::vars::longstring::LongString;
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+
+macro_rules! trigger_async_listeners {
+    ($ext:ident.$listeners:ident$(, $args:expr)*) => {
+        for listener in $ext.$listeners.iter_mut() {
+            (listener)($ext.handle.clone()$(, $args)*).await
+        }
+    }
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type AsyncListener<W> = Box<dyn FnMut(AsyncExtensionHandle<W>) -> BoxFuture<()> + Send>;
+type AsyncListenerWith<W, A> = Box<dyn FnMut(AsyncExtensionHandle<W>, A) -> BoxFuture<()> + Send>;
+type AsyncInterceptListener<W> = Box<dyn FnMut(AsyncExtensionHandle<W>, HMessage) -> BoxFuture<HMessage> + Send>;
+type AsyncFlagsCallback<W> = Box<dyn FnOnce(AsyncExtensionHandle<W>, Vec<String>) -> BoxFuture<()> + Send>;
+
+/// Async counterpart of [`ExtensionHandle`](super::extensionhandle::ExtensionHandle),
+/// passed by value to every [`AsyncExtension`] listener so it can be held across `.await` points
+pub struct AsyncExtensionHandle<W> {
+    name: Arc<RwLock<String>>,
+    writer: Arc<RwLock<Option<AsyncGEarthWriter>>>,
+    packet_info_manager: Arc<RwLock<Option<PacketInfoManager>>>,
+    flag_callback: Arc<std::sync::Mutex<Option<AsyncFlagsCallback<W>>>>,
+    globals: Arc<Mutex<W>>
+}
+
+impl <W> Clone for AsyncExtensionHandle<W> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            writer: self.writer.clone(),
+            packet_info_manager: self.packet_info_manager.clone(),
+            flag_callback: self.flag_callback.clone(),
+            globals: self.globals.clone()
+        }
+    }
+}
+
+impl <W: Debug> Debug for AsyncExtensionHandle<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncExtensionHandle")
+            .field("name", &self.name)
+            .field("globals", &self.globals)
+            .finish()
+    }
+}
+
+impl <W: Send + 'static> AsyncExtensionHandle<W> {
+    fn new(globals: W) -> Self {
+        Self {
+            name: Arc::new(RwLock::new(String::new())),
+            writer: Arc::new(RwLock::new(None)),
+            packet_info_manager: Arc::new(RwLock::new(None)),
+            flag_callback: Arc::new(std::sync::Mutex::new(None)),
+            globals: Arc::new(Mutex::new(globals))
+        }
+    }
+
+    fn set_name(&self, name: &str) {
+        let mut cur_name = self.name.write().unwrap();
+        if *cur_name != name {
+            *cur_name = name.to_string();
+        }
+    }
+
+    fn set_writer(&self, writer: Option<AsyncGEarthWriter>) {
+        *self.writer.write().unwrap() = writer;
+    }
+
+    fn set_packet_info_manager(&self, packet_info_manager: Option<PacketInfoManager>) {
+        *self.packet_info_manager.write().unwrap() = packet_info_manager;
+    }
+
+    fn writer(&self) -> Option<AsyncGEarthWriter> {
+        self.writer.read().unwrap().clone()
+    }
+
+    async fn write(&self, bytes: Vec<u8>) -> Result<()> {
+        self.writer().ok_or(Error::Disconnected)?.write(bytes).await
+    }
+
+    /// Shared extension state, lock it with `.lock().await`
+    pub fn globals(&self) -> Arc<Mutex<W>> {
+        self.globals.clone()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.writer.read().unwrap().is_some()
+    }
+
+    pub fn get_packet_info_manager(&self) -> Option<PacketInfoManager> {
+        self.packet_info_manager.read().unwrap().clone()
+    }
+
+    pub async fn write_to_console(&self, s: String) -> Result<()> {
+        self.write_to_console_formatted(s, ConsoleColour::White).await
+    }
+
+    pub async fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        let name = self.name.read().unwrap().clone();
+        self.writer().ok_or(Error::Disconnected)?
+            .write_to_console_formatted(format!("[{name}] {s}"), colour).await
+    }
+
+    /// Writes an error to the console in red, or logs it with [`log::error!`] when that fails
+    async fn report_error(&self, s: String) {
+        if let Err(e) = self.write_to_console_formatted(s.clone(), ConsoleColour::Red).await {
+            log::error!("{s} ({e})");
+        }
+    }
+
+    pub async fn request_flags<F, Fut>(&self, callback: F) -> Result<()>
+        where F: FnOnce(AsyncExtensionHandle<W>, Vec<String>) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        *self.flag_callback.lock().unwrap() = Some(Box::new(move | handle, flags | Box::pin(callback(handle, flags))));
+        let request_packet = HPacket::from_header_id(OutgoingMessageIds::REQUEST_FLAGS);
+        self.write(request_packet.get_bytes()).await
+    }
+
+    pub async fn send_to_client(&self, packet: HPacket) -> Result<()> {
+        self.send_internal(packet, HDirection::ToClient).await
+    }
+
+    pub async fn send_to_server(&self, packet: HPacket) -> Result<()> {
+        self.send_internal(packet, HDirection::ToServer).await
+    }
+
+    async fn send_internal(&self, packet: HPacket, direction: HDirection) -> Result<()> {
+        let writer = self.writer().ok_or(Error::Disconnected)?;
+        let sending_packet = wrap_send_packet(packet, direction, self.get_packet_info_manager())?;
+        writer.write(sending_packet.get_bytes()).await
+    }
+
+    pub async fn send<B: BaseParser>(&self, packet_object: B) -> Result<()> {
+        let header_id = resolve_header_id::<B>(self.get_packet_info_manager())?;
+        self.send_with_id(packet_object, header_id).await
+    }
+
+    pub async fn send_with_id<B: BaseParser>(&self, packet_object: B, header_id: i32) -> Result<()> {
+        let mut packet = HPacket::from_header_id(header_id as i16);
+        packet_object.append_to_packet(&mut packet);
+        self.send_internal(packet, B::get_direction()).await
+    }
+}
+
+/// An [`Extension`](super::extension::Extension) driven by a tokio runtime
+///
+/// Listeners receive an owned [`AsyncExtensionHandle`] and return a future, so they can `.await`
+/// between reading an intercepted packet and sending replies. Intercept listeners get the
+/// [`HMessage`] by value and hand it back once they're done with it.
+pub struct AsyncExtension<W: Debug + Send + 'static> {
+    pub info: ExtensionInfo,
+    pub capabilities: Capabilities,
+    pub args: Vec<String>,
+    pub port_source: PortSource,
+    arguments: Vec<Argument>,
+    /// Reconnects when the connection with G-Earth is lost if set, see [`run`](Self::run)
+    pub reconnect_policy: Option<ReconnectPolicy>,
+    handle: AsyncExtensionHandle<W>,
+
+    delayed_init: bool,
+    host_info: Option<HostInfo>,
+
+    on_init: Vec<AsyncListener<W>>,
+    on_connect: Vec<AsyncListenerWith<W, ConnectionInfo>>,
+    on_start: Vec<AsyncListener<W>>,
+    on_end: Vec<AsyncListener<W>>,
+    on_click: Vec<AsyncListener<W>>,
+    on_host_info_update: Vec<AsyncListenerWith<W, HostInfo>>,
+    on_socket_disconnect: Vec<AsyncListener<W>>,
+
+    intercepts: InterceptRegistry<AsyncInterceptListener<W>>
+}
+
+impl <W: Debug + Send + 'static> Debug for AsyncExtension<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncExtension")
+            .field("info", &self.info)
+            .field("args", &self.args)
+            .field("globals", &self.handle.globals)
+            .finish()
+    }
+}
+
+impl <W: Debug + Default + Send + 'static> Default for AsyncExtension<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <W: Debug + Default + Send + 'static> AsyncExtension<W> {
+    pub fn new() -> Self {
+        Self::with_globals(W::default())
+    }
+}
+
+impl <W: Debug + Send + 'static> AsyncExtension<W> {
+    pub fn with_globals(globals: W) -> Self {
+        AsyncExtension {
+            info: ExtensionInfo::default(),
+            capabilities: Capabilities::default(),
+            args: env::args().collect(),
+            port_source: PortSource::default(),
+            arguments: Vec::new(),
+            reconnect_policy: None,
+            handle: AsyncExtensionHandle::new(globals),
+
+            delayed_init: false,
+            host_info: None,
+
+            on_init: Vec::new(),
+            on_connect: Vec::new(),
+            on_start: Vec::new(),
+            on_end: Vec::new(),
+            on_click: Vec::new(),
+            on_host_info_update: Vec::new(),
+            on_socket_disconnect: Vec::new(),
+
+            intercepts: InterceptRegistry::default()
+        }
+    }
+
+    /// Connects to G-Earth and handles its messages until the connection closes
+    ///
+    /// Returns `Ok` when G-Earth closed the connection and an error when connecting, reading or writing failed.
+    /// Reconnects like [`Extension::run`](super::extension::Extension::run) with a [`reconnect_policy`](Self::reconnect_policy).
+    pub async fn run(&mut self) -> Result<()> {
+        if self.handle.is_connected() {
+            return Ok(());
+        }
+
+        let mut attempt = 0;
+        loop {
+            let res = match self.port() {
+                Ok(port) => match AsyncGEarthConnection::new(port.to_string()).await {
+                    Ok(connection) => {
+                        attempt = 0;
+                        self.run_with_connection(connection).await
+                    },
+                    Err(e) => Err(e)
+                },
+                Err(e) => Err(e)
+            };
+            if res.as_ref().is_err_and(| e | !e.is_connection_error()) {
+                return res;
+            }
+            let Some(delay) = self.reconnect_policy.as_ref().and_then(| policy | policy.delay(attempt)) else {
+                return res;
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn port(&self) -> Result<u16> {
+        self.port_source.port(&self.args, &self.arguments)
+    }
+
+    /// Declares an argument the extension can be started with, read it with [`arguments`](Self::arguments)
+    pub fn add_argument(&mut self, argument: Argument) {
+        self.arguments.push(argument);
+    }
+
+    /// Parses [`args`](Self::args) for G-Earth's arguments and the ones added with [`add_argument`](Self::add_argument)
+    pub fn arguments(&self) -> Result<Arguments> {
+        Arguments::parse(&self.args, &self.arguments)
+    }
+
+    async fn run_with_connection(&mut self, mut connection: AsyncGEarthConnection) -> Result<()> {
+        self.handle.set_name(&self.info.name);
+        self.handle.set_writer(Some(connection.writer()));
+        let res = loop {
+            let packet = match connection.read_packet().await {
+                Ok(packet) => packet,
+                Err(e) => break Err(e)
+            };
+            if let Err(e) = self.on_g_packet(packet).await {
+                break Err(e);
+            }
+        };
+        self.handle.set_writer(None);
+        self.handle.set_packet_info_manager(None);
+        self.intercepts.set_packet_info_manager(None);
+        trigger_async_listeners!(self.on_socket_disconnect);
+
+        match res {
+            Err(Error::Disconnected) => Ok(()),
+            res => res
+        }
+    }
+
+    pub fn handle(&self) -> AsyncExtensionHandle<W> {
+        self.handle.set_name(&self.info.name);
+        self.handle.clone()
+    }
+
+    pub fn globals(&self) -> Arc<Mutex<W>> {
+        self.handle.globals()
+    }
+
+    pub fn get_packet_info_manager(&self) -> Option<PacketInfoManager> {
+        self.handle.get_packet_info_manager()
+    }
+
+    pub fn get_host_info(&self) -> Option<HostInfo> {
+        self.host_info.clone()
+    }
+
+    async fn on_g_packet(&mut self, packet: HPacket) -> Result<()> {
+        self.handle.set_name(&self.info.name);
+        let header_id = packet.header_id();
+        match header_id {
+            IncomingMessageIds::INFO_REQUEST => self.on_info_request_packet().await?,
+            IncomingMessageIds::CONNECTION_START => self.on_connection_start_packet(packet).await?,
+            IncomingMessageIds::CONNECTION_END => self.on_connection_end_packet().await,
+            IncomingMessageIds::FLAGS_CHECK => self.on_flags_check_packet(packet).await?,
+            IncomingMessageIds::INIT => self.on_init_packet(packet).await?,
+            IncomingMessageIds::ON_DOUBLE_CLICK => self.on_double_click_packet().await,
+            IncomingMessageIds::PACKET_INTERCEPT => self.on_packet_intercept_packet(packet).await?,
+            IncomingMessageIds::UPDATE_HOST_INFO => self.on_update_host_info_packet(packet).await?,
+            _ => self.handle.report_error(format!("Unknown incoming message {header_id}")).await
+        }
+        Ok(())
+    }
+
+    async fn on_info_request_packet(&mut self) -> Result<()> {
+        let arguments = self.arguments()?;
+        let file = arguments.file().unwrap_or_default().to_string();
+        let cookie = arguments.cookie().unwrap_or_default().to_string();
+        let mut response = HPacket::from_header_id(OutgoingMessageIds::EXTENSION_INFO);
+        response.append((
+            self.info.name.clone(),
+            self.info.author.clone(),
+            self.info.version.clone(),
+            self.info.description.clone(),
+            self.capabilities.clickable.unwrap_or(!self.on_click.is_empty()), // onclick
+            !file.is_empty(), // file == null
+            file, // file
+            cookie, // cookie
+            self.capabilities.can_leave, // can leave
+            self.capabilities.can_delete // can delete
+        ));
+        self.handle.write(response.get_bytes()).await
+    }
+
+    async fn on_connection_start_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        let connection_info: ConnectionInfo = packet.try_read_field("connection info")?;
+        self.handle.set_packet_info_manager(packet.try_read_field("packet info")?);
+        self.intercepts.set_packet_info_manager(self.handle.get_packet_info_manager());
+        *CUR_CLIENT.lock().unwrap() = connection_info.client.clone();
+
+        if self.delayed_init {
+            trigger_async_listeners!(self.on_init);
+            self.delayed_init = false;
+        }
+
+        trigger_async_listeners!(self.on_connect, connection_info.clone());
+        trigger_async_listeners!(self.on_start);
+        Ok(())
+    }
+
+    async fn on_connection_end_packet(&mut self) {
+        self.handle.set_packet_info_manager(None);
+        self.intercepts.set_packet_info_manager(None);
+        trigger_async_listeners!(self.on_end);
+    }
+
+    async fn on_flags_check_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        let callback = self.handle.flag_callback.lock().unwrap().take();
+        if let Some(callback) = callback {
+            let count: i32 = packet.try_read_field("count")?;
+            let mut flags: Vec<String> = Vec::new();
+            for i in 0..count {
+                flags.push(packet.try_read_field(format!("flags[{i}]"))?);
+            }
+            (callback)(self.handle.clone(), flags).await;
+        }
+        Ok(())
+    }
+
+    async fn on_init_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        (self.delayed_init, self.host_info) = packet.try_read()?;
+        if let Some(host_info) = self.host_info.clone() {
+            trigger_async_listeners!(self.on_host_info_update, host_info.clone());
+        }
+        if !self.delayed_init {
+            trigger_async_listeners!(self.on_init);
+        }
+
+        self.handle.writer().ok_or(Error::Disconnected)?
+            .write_to_console_formatted(format!("Extension \"{}\" successfully initialized", self.info.name), ConsoleColour::Green).await
+    }
+
+    async fn on_double_click_packet(&mut self) {
+        trigger_async_listeners!(self.on_click);
+    }
+
+    async fn on_packet_intercept_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        let string_message: LongString = packet.try_read_field("message")?;
+        let h_message = HMessage::from_string(string_message.clone());
+
+        let mut h_message = self.modify_message(h_message).await;
+
+        let mut response_packet = HPacket::from_header_id(OutgoingMessageIds::MANIPULATED_PACKET);
+        response_packet.append(LongString(h_message.stringify()));
+
+        self.handle.write(response_packet.get_bytes()).await
+    }
+
+    async fn modify_message(&mut self, mut msg: HMessage) -> HMessage {
+        let direction = msg.get_destination();
+        let header_id = msg.get_packet().header_id();
+        let Some(handles) = self.intercepts.matching(&direction, header_id) else {
+            return msg;
+        };
+        for handle in handles.iter().copied() {
+            if let Some(mut listener) = self.intercepts.take(handle) {
+                msg = (listener)(self.handle.clone(), msg).await;
+                self.intercepts.restore(handle, listener);
+            }
+        }
+
+        msg
+    }
+
+    async fn on_update_host_info_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        self.host_info = packet.try_read()?;
+        if let Some(host_info) = self.host_info.clone() {
+            trigger_async_listeners!(self.on_host_info_update, host_info.clone());
+        }
+        Ok(())
+    }
+
+    pub async fn write_to_console(&self, s: String) -> Result<()> {
+        self.handle().write_to_console(s).await
+    }
+
+    pub async fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        self.handle().write_to_console_formatted(s, colour).await
+    }
+
+    pub fn on_init<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_init.push(Box::new(move | handle | Box::pin(listener(handle))));
+    }
+
+    pub fn on_socket_disconnect<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_socket_disconnect.push(Box::new(move | handle | Box::pin(listener(handle))));
+    }
+
+    pub fn on_connect<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>, ConnectionInfo) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_connect.push(Box::new(move | handle, connection_info | Box::pin(listener(handle, connection_info))));
+    }
+
+    pub fn on_start<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_start.push(Box::new(move | handle | Box::pin(listener(handle))));
+    }
+
+    pub fn on_end<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_end.push(Box::new(move | handle | Box::pin(listener(handle))));
+    }
+
+    pub fn on_host_info_update<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>, HostInfo) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_host_info_update.push(Box::new(move | handle, host_info | Box::pin(listener(handle, host_info))));
+    }
+
+    pub fn on_click<F, Fut>(&mut self, mut listener: F)
+        where F: FnMut(AsyncExtensionHandle<W>) -> Fut + Send + 'static,
+              Fut: Future<Output = ()> + Send + 'static {
+        self.on_click.push(Box::new(move | handle | Box::pin(listener(handle))));
+    }
+
+    /// Intercepts packets of type `T`, the listener hands back the message and the (possibly edited) object
+    pub fn intercept<T, F, Fut>(&mut self, listener: F) -> InterceptHandle
+        where T: BaseParser + Send + 'static,
+              F: FnMut(AsyncExtensionHandle<W>, HMessage, T) -> Fut + Send + 'static,
+              Fut: Future<Output = (HMessage, T)> + Send + 'static {
+        self.intercept_with_priority(DEFAULT_PRIORITY, listener)
+    }
+
+    pub fn intercept_with_priority<T, F, Fut>(&mut self, priority: i32, listener: F) -> InterceptHandle
+        where T: BaseParser + Send + 'static,
+              F: FnMut(AsyncExtensionHandle<W>, HMessage, T) -> Fut + Send + 'static,
+              Fut: Future<Output = (HMessage, T)> + Send + 'static {
+        self.register_intercept(T::get_direction(), T::get_packet_name(), priority, Self::wrap_listener(listener))
+    }
+
+    pub fn intercept_by_id<T, F, Fut>(&mut self, id: i32, listener: F) -> InterceptHandle
+        where T: BaseParser + Send + 'static,
+              F: FnMut(AsyncExtensionHandle<W>, HMessage, T) -> Fut + Send + 'static,
+              Fut: Future<Output = (HMessage, T)> + Send + 'static {
+        self.register_intercept(T::get_direction(), id, DEFAULT_PRIORITY, Self::wrap_listener(listener))
+    }
+
+    fn wrap_listener<T, F, Fut>(mut listener: F) -> AsyncInterceptListener<W>
+        where T: BaseParser + Send + 'static,
+              F: FnMut(AsyncExtensionHandle<W>, HMessage, T) -> Fut + Send + 'static,
+              Fut: Future<Output = (HMessage, T)> + Send + 'static {
+        Box::new(move | handle: AsyncExtensionHandle<W>, mut msg: HMessage | {
+            let mut original_packet = msg.get_packet().clone();
+            let object = match T::try_parse(&mut original_packet) {
+                Ok(object) => object,
+                Err(e) => return Box::pin(async move {
+                    // Leave the packet alone so it still reaches the other listeners and its destination
+                    handle.report_error(format!("Couldn't parse {}: {e}", T::get_packet_name())).await;
+                    msg
+                })
+            };
+            let original_object = object.clone();
+            let future = listener(handle, msg, object);
+
+            Box::pin(async move {
+                let (mut msg, object) = future.await;
+                if original_object != object {
+                    msg.get_packet().replace(6, object);
+                }
+                msg
+            })
+        })
+    }
+
+    pub fn intercept_raw<I, F, Fut>(&mut self, direction: HDirection, indicator: I, listener: F) -> InterceptHandle
+        where I: InterceptIndicator,
+              F: FnMut(AsyncExtensionHandle<W>, HMessage) -> Fut + Send + 'static,
+              Fut: Future<Output = HMessage> + Send + 'static {
+        self.intercept_raw_with_priority(direction, indicator, DEFAULT_PRIORITY, listener)
+    }
+
+    /// Same calling order as [`Extension::intercept_raw_with_priority`](super::extension::Extension::intercept_raw_with_priority)
+    pub fn intercept_raw_with_priority<I, F, Fut>(&mut self, direction: HDirection, indicator: I, priority: i32, mut listener: F) -> InterceptHandle
+        where I: InterceptIndicator,
+              F: FnMut(AsyncExtensionHandle<W>, HMessage) -> Fut + Send + 'static,
+              Fut: Future<Output = HMessage> + Send + 'static {
+        self.register_intercept(direction, indicator, priority, Box::new(move | handle, msg | Box::pin(listener(handle, msg))))
+    }
+
+    fn register_intercept<I: InterceptIndicator>(&mut self, direction: HDirection, indicator: I, priority: i32, listener: AsyncInterceptListener<W>) -> InterceptHandle {
+        self.intercepts.add(direction, InterceptTarget::from_indicator(indicator), priority, listener)
+    }
+
+    /// Unregisters an intercept listener, returns `false` if it was already removed
+    pub fn remove_intercept(&mut self, handle: InterceptHandle) -> bool {
+        self.intercepts.remove(handle)
+    }
+
+    pub async fn send_to_client(&self, packet: HPacket) -> Result<()> {
+        self.handle.send_to_client(packet).await
+    }
+
+    pub async fn send_to_server(&self, packet: HPacket) -> Result<()> {
+        self.handle.send_to_server(packet).await
+    }
+
+    pub async fn send<B: BaseParser>(&self, packet_object: B) -> Result<()> {
+        self.handle.send(packet_object).await
+    }
+
+    pub async fn send_with_id<B: BaseParser>(&self, packet_object: B, header_id: i32) -> Result<()> {
+        self.handle.send_with_id(packet_object, header_id).await
+    }
+}
@@ -0,0 +1,146 @@
+use std::env;
+use std::fmt::Debug;
+use super::arguments::Argument;
+use super::extension::{Capabilities, Extension, ExtensionInfo, PortSource};
+use super::reconnect::ReconnectPolicy;
+
+/// Configures an [`Extension`] before it's created, see [`Extension::builder`]
+///
+/// ```no_run
+/// # use g_rust::extension::extension::Extension;
+/// # use g_rust::extension_info;
+/// #[derive(Debug)]
+/// struct Globals {
+///     prefix: String
+/// }
+///
+/// let mut ext = Extension::builder()
+///     .info(extension_info!())
+///     .can_delete(false)
+///     .globals(Globals { prefix: String::from(":") })
+///     .build();
+/// ext.run().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ExtensionBuilder<W: Debug> {
+    info: ExtensionInfo,
+    capabilities: Capabilities,
+    args: Vec<String>,
+    arguments: Vec<Argument>,
+    port_source: PortSource,
+    reconnect_policy: Option<ReconnectPolicy>,
+    globals: W
+}
+
+impl Default for ExtensionBuilder<()> {
+    fn default() -> Self {
+        Self {
+            info: ExtensionInfo::default(),
+            capabilities: Capabilities::default(),
+            args: env::args().collect(),
+            arguments: Vec::new(),
+            port_source: PortSource::default(),
+            reconnect_policy: None,
+            globals: ()
+        }
+    }
+}
+
+impl <W: Debug + 'static> ExtensionBuilder<W> {
+    /// Sets name, description, author and version at once, e.g. from [`extension_info!`](crate::extension_info)
+    pub fn info(mut self, info: ExtensionInfo) -> Self {
+        self.info = info;
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.info.name = name.into();
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.info.description = description.into();
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.info.author = author.into();
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.info.version = version.into();
+        self
+    }
+
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Whether the extension can be disconnected from G-Earth, `true` by default
+    pub fn can_leave(mut self, can_leave: bool) -> Self {
+        self.capabilities.can_leave = can_leave;
+        self
+    }
+
+    /// Whether the extension can be removed from G-Earth, `true` by default
+    pub fn can_delete(mut self, can_delete: bool) -> Self {
+        self.capabilities.can_delete = can_delete;
+        self
+    }
+
+    /// Whether the extension has a button to click, by default only if there's an `on_click` listener
+    pub fn clickable(mut self, clickable: bool) -> Self {
+        self.capabilities.clickable = Some(clickable);
+        self
+    }
+
+    /// Command-line arguments to parse instead of the ones the program was started with
+    pub fn args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Declares an extra argument, see [`Extension::add_argument`]
+    pub fn argument(mut self, argument: Argument) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+
+    pub fn port(mut self, port_source: PortSource) -> Self {
+        self.port_source = port_source;
+        self
+    }
+
+    pub fn reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = Some(reconnect_policy);
+        self
+    }
+
+    /// Initial globals of the extension, these don't need to implement `Default`
+    pub fn globals<G: Debug + 'static>(self, globals: G) -> ExtensionBuilder<G> {
+        ExtensionBuilder {
+            info: self.info,
+            capabilities: self.capabilities,
+            args: self.args,
+            arguments: self.arguments,
+            port_source: self.port_source,
+            reconnect_policy: self.reconnect_policy,
+            globals
+        }
+    }
+
+    pub fn build(self) -> Extension<W> {
+        let mut ext = Extension::with_globals(self.globals);
+        ext.info = self.info;
+        ext.capabilities = self.capabilities;
+        ext.args = self.args;
+        ext.port_source = self.port_source;
+        ext.reconnect_policy = self.reconnect_policy;
+        for argument in self.arguments {
+            ext.add_argument(argument);
+        }
+        ext
+    }
+}
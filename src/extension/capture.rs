@@ -0,0 +1,232 @@
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::extension::Extension;
+use crate::error::{Error, Result};
+use crate::protocol::hdirection::HDirection;
+use crate::protocol::hmessage::HMessage;
+use crate::protocol::hpacket::HPacket;
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+
+/// First bytes of every capture file, followed by [`CAPTURE_VERSION`]
+pub const CAPTURE_MAGIC: &[u8; 5] = b"GRCAP";
+pub const CAPTURE_VERSION: u8 = 1;
+
+/// Header id of a record holding the [`PacketInfoManager`] of the connection
+const PACKET_INFO_RECORD: i16 = 1;
+/// Header id of a record holding a [`CapturedMessage`]
+const MESSAGE_RECORD: i16 = 2;
+
+/// An intercepted message as it was recorded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedMessage {
+    pub timestamp: SystemTime,
+    pub index: i32,
+    pub direction: HDirection,
+    pub header_id: i16,
+    /// Name of the packet, if the packet info manager knew it
+    pub name: Option<String>,
+    /// The packet as G-Earth sent it, before any listener changed it
+    pub packet: HPacket,
+    /// Whether the listeners blocked the message
+    pub blocked: bool,
+    /// Whether the listeners changed the packet
+    pub modified: bool
+}
+
+impl CapturedMessage {
+    /// The message as G-Earth sent it, to feed through the intercepts again
+    pub fn to_message(&self) -> HMessage {
+        HMessage::from_packet_dir_index(self.packet.clone(), self.direction.clone(), self.index)
+    }
+
+    fn to_record(&self) -> HPacket {
+        let timestamp = self.timestamp.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
+        let mut record = HPacket::from_header_id(MESSAGE_RECORD);
+        record.append((
+            timestamp,
+            self.index,
+            self.direction.clone(),
+            self.blocked,
+            self.modified,
+            self.name.clone().unwrap_or_default(),
+            self.packet.get_bytes()
+        ));
+        record
+    }
+
+    fn from_record(record: &mut HPacket) -> Result<Self> {
+        let (timestamp, index, direction, blocked, modified, name, bytes): (i64, i32, HDirection, bool, bool, String, Vec<u8>) = record.try_read()?;
+        let packet = HPacket::from_bytes(bytes);
+        Ok(Self {
+            timestamp: UNIX_EPOCH + Duration::from_millis(timestamp.max(0) as u64),
+            index,
+            direction,
+            header_id: packet.header_id(),
+            name: Some(name).filter(| name | !name.is_empty()),
+            packet,
+            blocked,
+            modified
+        })
+    }
+}
+
+/// A record of a capture file
+#[derive(Debug, Clone)]
+pub enum CaptureRecord {
+    /// Packet info of the connection the following messages were captured on
+    PacketInfo(PacketInfoManager),
+    Message(CapturedMessage)
+}
+
+/// Writes intercepted messages to a capture file, see [`Extension::start_recording`]
+///
+/// A capture file starts with [`CAPTURE_MAGIC`] and [`CAPTURE_VERSION`], followed by records framed like packets:
+/// a length, a header id telling the kind of record and the record's values
+pub struct Recorder {
+    writer: Box<dyn Write + Send>
+}
+
+impl Recorder {
+    /// Creates (or truncates) the capture file at `path`
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(| e | Error::Capture(format!("couldn't create {}: {e}", path.display())))?;
+        Self::new(BufWriter::new(file))
+    }
+
+    pub fn new(writer: impl Write + Send + 'static) -> Result<Self> {
+        let mut res = Self {
+            writer: Box::new(writer)
+        };
+        res.write(&[CAPTURE_MAGIC.as_slice(), &[CAPTURE_VERSION]].concat())?;
+        Ok(res)
+    }
+
+    pub fn record_packet_info(&mut self, packet_info_manager: &PacketInfoManager) -> Result<()> {
+        let mut record = HPacket::from_header_id(PACKET_INFO_RECORD);
+        record.append(packet_info_manager.clone());
+        self.write(record.bytes())
+    }
+
+    pub fn record(&mut self, message: &CapturedMessage) -> Result<()> {
+        self.write(message.to_record().bytes())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(| e | Error::Capture(format!("couldn't write capture: {e}")))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes).map_err(| e | Error::Capture(format!("couldn't write capture: {e}")))
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// The outcome of feeding a [`CapturedMessage`] through an extension again
+#[derive(Debug, Clone)]
+pub struct ReplayedMessage {
+    pub captured: CapturedMessage,
+    /// The message after the extension's listeners handled it
+    pub message: HMessage,
+    pub modified: bool
+}
+
+impl ReplayedMessage {
+    /// Whether the listeners blocked and modified the message like they did when it was captured
+    pub fn matches_capture(&self) -> bool {
+        self.message.blocked == self.captured.blocked && self.modified == self.captured.modified
+    }
+}
+
+/// Reads a capture file written by a [`Recorder`], record by record
+pub struct Replayer<R: Read> {
+    reader: R
+}
+
+impl Replayer<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(| e | Error::Capture(format!("couldn't open {}: {e}", path.display())))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl <R: Read> Replayer<R> {
+    /// Checks the capture header, fails if `reader` isn't a capture of a known version
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 6];
+        reader.read_exact(&mut header).map_err(| e | Error::Capture(format!("couldn't read capture header: {e}")))?;
+        if &header[..5] != CAPTURE_MAGIC {
+            return Err(Error::Capture(String::from("not a capture file")));
+        }
+        if header[5] != CAPTURE_VERSION {
+            return Err(Error::Capture(format!("unsupported capture version {}", header[5])));
+        }
+        Ok(Self { reader })
+    }
+
+    /// The next record, `None` at the end of the capture
+    pub fn next_record(&mut self) -> Result<Option<CaptureRecord>> {
+        let mut length = [0u8; 4];
+        match self.reader.read_exact(&mut length) {
+            Ok(()) => {},
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(Error::Capture(format!("couldn't read capture: {e}")))
+        }
+        let length = i32::from_be_bytes(length);
+        if length < 2 {
+            return Err(Error::Capture(format!("invalid record length {length}")));
+        }
+
+        let mut bytes = length.to_be_bytes().to_vec();
+        bytes.resize(4 + length as usize, 0);
+        self.reader.read_exact(&mut bytes[4..]).map_err(| e | Error::Capture(format!("truncated record: {e}")))?;
+
+        let mut record = HPacket::from_bytes(bytes);
+        match record.header_id() {
+            PACKET_INFO_RECORD => Ok(Some(CaptureRecord::PacketInfo(record.try_read()?))),
+            MESSAGE_RECORD => Ok(Some(CaptureRecord::Message(CapturedMessage::from_record(&mut record)?))),
+            kind => Err(Error::Capture(format!("unknown record kind {kind}")))
+        }
+    }
+
+    /// Feeds every captured message through the intercepts of `ext`, as if G-Earth sent them
+    ///
+    /// The packet info of the capture is given to `ext` so intercepts by name match. Nothing is sent to G-Earth,
+    /// listeners sending packets get [`Error::Disconnected`]
+    pub fn replay<W: Debug + 'static>(&mut self, ext: &mut Extension<W>) -> Result<Vec<ReplayedMessage>> {
+        let mut res = Vec::new();
+        while let Some(record) = self.next_record()? {
+            match record {
+                CaptureRecord::PacketInfo(packet_info_manager) => ext.set_packet_info_manager(Some(packet_info_manager)),
+                CaptureRecord::Message(captured) => {
+                    let mut message = captured.to_message();
+                    ext.dispatch_intercept(&mut message);
+                    let modified = message.get_packet().bytes() != captured.packet.bytes();
+                    res.push(ReplayedMessage {
+                        captured,
+                        message,
+                        modified
+                    });
+                }
+            }
+        }
+        Ok(res)
+    }
+}
+
+impl <R: Read> Iterator for Replayer<R> {
+    type Item = Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
@@ -0,0 +1,215 @@
+use std::any::type_name;
+use std::str::FromStr;
+use crate::protocol::vars::legacy::LegacyId;
+
+pub(crate) type CommandHandler<E> = Box<dyn FnMut(&mut E, &[&str]) -> Result<(), String> + Send>;
+
+/// Priority of the intercepts handling commands, so they're blocked before other chat listeners see them
+pub const COMMAND_PRIORITY: i32 = i32::MAX;
+
+/// Name of the generated command listing all registered commands
+pub const HELP_COMMAND: &str = ":help";
+
+/// Arguments of a chat command, parsed from the words following the command name
+///
+/// Implemented for:
+/// - `()`: no arguments
+/// - tuples of up to 6 [`FromStr`] types: exactly that many arguments
+/// - `Vec<T: FromStr>`: any amount of arguments
+/// - `String`: the rest of the message
+pub trait CommandArgs: Sized {
+    fn parse(args: &[&str]) -> Result<Self, String>;
+
+    /// Describes the arguments in the `:help` listing, e.g. `<i32> <String>`
+    fn usage() -> String;
+}
+
+fn short_type_name<T>() -> &'static str {
+    let name = type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn parse_arg<T: FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse().map_err(| _ | format!("\"{arg}\" is not a valid {}", short_type_name::<T>()))
+}
+
+fn expect_arg_count(args: &[&str], count: usize) -> Result<(), String> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(format!("expected {count} argument(s), got {}", args.len()))
+    }
+}
+
+impl CommandArgs for () {
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        expect_arg_count(args, 0)
+    }
+
+    fn usage() -> String {
+        String::new()
+    }
+}
+
+impl CommandArgs for String {
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        if args.is_empty() {
+            Err("expected a message".to_string())
+        } else {
+            Ok(args.join(" "))
+        }
+    }
+
+    fn usage() -> String {
+        "<message>".to_string()
+    }
+}
+
+impl <T: FromStr> CommandArgs for Vec<T> {
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        args.iter().map(| arg | parse_arg(arg)).collect()
+    }
+
+    fn usage() -> String {
+        format!("[<{}>...]", short_type_name::<T>())
+    }
+}
+
+macro_rules! impl_command_args_tuple {
+    ($($size:expr, $($ty:ident:$n:tt),+);+;) => ($(
+        impl <$($ty: FromStr),+> CommandArgs for ($($ty,)+) {
+            fn parse(args: &[&str]) -> Result<Self, String> {
+                expect_arg_count(args, $size)?;
+                Ok(($(parse_arg::<$ty>(args[$n])?,)+))
+            }
+
+            fn usage() -> String {
+                [$(format!("<{}>", short_type_name::<$ty>())),+].join(" ")
+            }
+        }
+    )+)
+}
+
+impl_command_args_tuple! {
+    1, T0:0;
+    2, T0:0, T1:1;
+    3, T0:0, T1:1, T2:2;
+    4, T0:0, T1:1, T2:2, T3:3;
+    5, T0:0, T1:1, T2:2, T3:3, T4:4;
+    6, T0:0, T1:1, T2:2, T3:3, T4:4, T5:5;
+}
+
+/// A registered chat command, returned by [`Extension::command`](super::extension::Extension::command) to add aliases and a description
+pub struct Command<E> {
+    names: Vec<String>,
+    usage: String,
+    description: String,
+    handler: Option<CommandHandler<E>>
+}
+
+impl <E> Command<E> {
+    /// Adds another name the command can be called by
+    pub fn alias(&mut self, alias: impl Into<String>) -> &mut Self {
+        self.names.push(alias.into());
+        self
+    }
+
+    /// Sets the description shown by `:help`
+    pub fn description(&mut self, description: impl Into<String>) -> &mut Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.names.iter().any(| n | n.eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn usage(&self) -> String {
+        if self.usage.is_empty() {
+            self.names[0].clone()
+        } else {
+            format!("{} {}", self.names[0], self.usage)
+        }
+    }
+
+    fn help_line(&self) -> String {
+        let mut res = self.names.join(", ");
+        if !self.usage.is_empty() {
+            res += &format!(" {}", self.usage);
+        }
+        if !self.description.is_empty() {
+            res += &format!(" - {}", self.description);
+        }
+        res
+    }
+}
+
+pub(crate) struct CommandRegistry<E> {
+    commands: Vec<Command<E>>,
+    /// Whether the chat intercepts handling the commands are registered
+    pub installed: bool,
+    pub own_id: Option<LegacyId>,
+    /// Room index of the user, feedback whispers are sent as if they were said by it
+    pub own_room_index: Option<i32>
+}
+
+impl <E> Default for CommandRegistry<E> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            installed: false,
+            own_id: None,
+            own_room_index: None
+        }
+    }
+}
+
+impl <E> CommandRegistry<E> {
+    pub fn add<A: CommandArgs>(&mut self, name: String, handler: CommandHandler<E>) -> &mut Command<E> {
+        self.commands.push(Command {
+            names: vec![name],
+            usage: A::usage(),
+            description: String::new(),
+            handler: Some(handler)
+        });
+        self.commands.last_mut().unwrap()
+    }
+
+    /// Index of the command called `name` (or with `name` as alias)
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.commands.iter().position(| command | command.matches(name))
+    }
+
+    pub fn get(&self, index: usize) -> &Command<E> {
+        &self.commands[index]
+    }
+
+    /// Takes the handler out, so it can be called with the extension
+    ///
+    /// Returns `None` if the command is already running (it called itself)
+    pub fn take(&mut self, index: usize) -> Option<CommandHandler<E>> {
+        self.commands[index].handler.take()
+    }
+
+    pub fn restore(&mut self, index: usize, handler: CommandHandler<E>) {
+        self.commands[index].handler = Some(handler);
+    }
+
+    /// One line per command for the `:help` listing
+    pub fn help(&self) -> Vec<String> {
+        let mut lines = vec![format!("{HELP_COMMAND} - Lists all commands")];
+        lines.extend(self.commands.iter().map(| command | command.help_line()));
+        lines
+    }
+}
+
+/// Splits a chat message in the command name and its arguments
+pub(crate) fn split_command(text: &str) -> Option<(&str, Vec<&str>)> {
+    let mut words = text.split_whitespace();
+    let name = words.next()?;
+    Some((name, words.collect()))
+}
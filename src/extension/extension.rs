@@ -1,31 +1,61 @@
-use std::collections::HashMap;
 use std::env;
-use std::fmt::{Debug, Error, Formatter};
+use std::fmt::{Debug, Formatter};
+#[cfg(feature = "persist")]
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use super::arguments::{Argument, Arguments};
+use super::builder::ExtensionBuilder;
+use super::capture::{CapturedMessage, Recorder};
+use super::command::{split_command, Command, CommandArgs, CommandRegistry, COMMAND_PRIORITY, HELP_COMMAND};
+use super::extensionhandle::{packet_to_string_request, read_packet_to_string_response, read_string_to_packet_response, string_to_packet_request, ExtensionHandle, CONVERSION_TIMEOUT};
+use super::intercept::{InterceptHandle, InterceptRegistry, InterceptTarget, DEFAULT_PRIORITY};
 use super::parsers::baseparser::BaseParser;
+#[cfg(feature = "persist")]
+use super::persist;
+use super::parsers::{incoming, outgoing};
+use super::parsers::subparsers::User;
+use super::reconnect::{ConnectionKind, ReconnectPolicy};
+use super::scheduler::{Scheduler, TaskHandle};
+use super::sendqueue::{RateLimit, SendQueue, FLOOD_CONTROL_PRIORITY};
+#[cfg(feature = "tracing")]
+use super::tracinglayer::INTERCEPT_SPAN;
+use crate::error::{Error, Result};
 use crate::misc::connection::GEarthConnection;
 use crate::misc::connectioninfo::ConnectionInfo;
 use crate::misc::consoleformat::ConsoleColour;
 use crate::misc::hclient::CUR_CLIENT;
 use crate::misc::hostinfo::HostInfo;
+use crate::misc::transport;
+use crate::misc::transport::Transport;
 use crate::misc::messages::*;
 use crate::protocol::hdirection::HDirection;
 use crate::protocol::hmessage::HMessage;
 use crate::protocol::hpacket::HPacket;
-use crate::protocol::vars::packetvariable::PacketVariable;
 use crate::protocol::vars::longstring::LongString;
 use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+#[cfg(feature = "persist")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "persist")]
+use serde::Serialize;
 
 macro_rules! trigger_listeners {
-    ($listener:expr$(, $args:expr)*) => {
-        for listener in $listener.clone().iter() {
-            (listener)($($args),*)
+    ($ext:ident.$listeners:ident$(, $args:expr)*) => {{
+        let mut listeners = std::mem::take(&mut $ext.$listeners);
+        for listener in listeners.iter_mut() {
+            (listener)($ext$(, $args)*)
         }
-    }
+        listeners.append(&mut $ext.$listeners);
+        $ext.$listeners = listeners;
+    }}
 }
 
-const PORT_FLAG: [&str; 2] = ["--port", "-p"];
-const FILE_FLAG: [&str; 2] = ["--filename", "-f"];
-const COOKIE_FLAG: [&str; 2] = ["--auth-token", "-c"];
+type Listener<E> = Box<dyn FnMut(&mut E) + Send>;
+type ListenerWith<E, A> = Box<dyn FnMut(&mut E, A) + Send>;
+type InterceptListener<E> = Box<dyn FnMut(&mut E, &mut HMessage) + Send>;
+type FlagsCallback<E> = Box<dyn FnOnce(&mut E, Vec<String>) + Send>;
 
 #[derive(Debug, Clone)]
 pub struct ExtensionInfo {
@@ -46,31 +76,105 @@ impl Default for ExtensionInfo {
     }
 }
 
-pub struct Extension<W: Debug + Default> {
+/// Fills an [`ExtensionInfo`] with the name, description, authors and version from the calling crate's `Cargo.toml`
+///
+/// Unlike [`ExtensionInfo::default`] these are read at compile time, so they're also set outside of `cargo run`
+#[macro_export]
+macro_rules! extension_info {
+    () => {
+        $crate::extension::extension::ExtensionInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            description: env!("CARGO_PKG_DESCRIPTION").to_string(),
+            author: env!("CARGO_PKG_AUTHORS").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string()
+        }
+    }
+}
+
+/// What G-Earth lets the user do with the extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether the extension can be disconnected from G-Earth
+    pub can_leave: bool,
+    /// Whether the extension can be removed from G-Earth
+    pub can_delete: bool,
+    /// Whether the extension has a button to click, by default only if there's an [`on_click`](Extension::on_click) listener
+    pub clickable: Option<bool>
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            can_leave: true,
+            can_delete: true,
+            clickable: None
+        }
+    }
+}
+
+/// Where [`Extension::run`] gets the port to connect to G-Earth on from, for the first connection and every reconnect
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PortSource {
+    /// `--port`/`-p` or `G_EARTH_PORT`, see [`Arguments::port`]
+    #[default]
+    Arguments,
+    Fixed(u16),
+    /// The given environment variable
+    Env(String)
+}
+
+impl PortSource {
+    /// The port, `args` are parsed with `arguments` for [`PortSource::Arguments`]
+    pub(crate) fn port(&self, args: &[String], arguments: &[Argument]) -> Result<u16> {
+        match self {
+            PortSource::Arguments => Arguments::parse(args, arguments)?.port(),
+            PortSource::Fixed(port) => Ok(*port),
+            PortSource::Env(var) => {
+                let port = env::var(var).map_err(| _ | Error::Arguments(format!("{var} is not set")))?;
+                port.parse().map_err(| _ | Error::Arguments(format!("\"{port}\" in {var} is not a valid port")))
+            }
+        }
+    }
+}
+
+pub struct Extension<W: Debug> {
     pub info: ExtensionInfo,
+    pub capabilities: Capabilities,
     pub args: Vec<String>,
+    pub port_source: PortSource,
     pub globals: W,
-    connection: Option<GEarthConnection>,
-    packet_info_manager: Option<PacketInfoManager>,
-
+    arguments: Vec<Argument>,
+    /// Reconnects when the connection with G-Earth is lost if set, see [`run`](Self::run)
+    pub reconnect_policy: Option<ReconnectPolicy>,
+    handle: ExtensionHandle,
+    #[cfg(feature = "persist")]
+    state_path: Option<PathBuf>,
+    recorder: Option<Recorder>,
+
+    connected_before: bool,
     delayed_init: bool,
     host_info: Option<HostInfo>,
 
-    on_init: Vec<fn(&mut Self)>,
-    on_connect: Vec<fn(&mut Self, ConnectionInfo)>,
-    on_start: Vec<fn(&mut Self)>,
-    on_end: Vec<fn(&mut Self)>,
-    on_click: Vec<fn(&mut Self)>,
-    on_host_info_update: Vec<fn(&mut Self, HostInfo)>,
-    on_socket_disconnect: Vec<fn(&mut Self)>,
+    on_init: Vec<Listener<Self>>,
+    on_connect: Vec<ListenerWith<Self, ConnectionInfo>>,
+    on_start: Vec<Listener<Self>>,
+    on_end: Vec<Listener<Self>>,
+    on_click: Vec<Listener<Self>>,
+    on_host_info_update: Vec<ListenerWith<Self, HostInfo>>,
+    on_socket_connect: Vec<ListenerWith<Self, ConnectionKind>>,
+    on_socket_disconnect: Vec<Listener<Self>>,
+
+    intercepts: InterceptRegistry<InterceptListener<Self>>,
+    commands: CommandRegistry<Self>,
 
-    intercepts_by_id: HashMap<HDirection, HashMap<i16, Vec<Box<dyn Fn(&mut Self, &mut HMessage) + Send + Sync>>>>,
-    intercepts_by_name: HashMap<HDirection, HashMap<String, Vec<Box<dyn Fn(&mut Self, &mut HMessage) + Send + Sync>>>>,
+    flag_callback: Option<FlagsCallback<Self>>,
 
-    flag_callback: Option<fn(&mut Self, Vec<String>)>
+    scheduler: Scheduler<Self>,
+    send_queue: SendQueue<Self>,
+    packet_receiver: Option<Receiver<Result<HPacket>>>
 }
 
-impl <W: Debug + Default> Debug for Extension<W> {
+impl <W: Debug> Debug for Extension<W> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Extension")
             .field("info", &self.info)
@@ -80,15 +184,88 @@ impl <W: Debug + Default> Debug for Extension<W> {
     }
 }
 
+impl Extension<()> {
+    /// Configures an extension step by step, see [`ExtensionBuilder`]
+    pub fn builder() -> ExtensionBuilder<()> {
+        ExtensionBuilder::default()
+    }
+}
+
 impl <W: Debug + Default + 'static> Extension<W> {
     pub fn new() -> Self {
+        Self::with_globals(W::default())
+    }
+}
+
+#[cfg(feature = "persist")]
+impl <W: Debug + Default + Serialize + DeserializeOwned + 'static> Extension<W> {
+    /// Loads the globals saved at [`persist::state_path`] for this extension's name and saves them there again
+    /// on [`on_end`](Self::on_end), on socket disconnect and with [`save_state`](Self::save_state)
+    ///
+    /// Call it after setting the name. Saved state that doesn't match `W` is replaced by `W::default()` with a warning
+    pub fn persist(&mut self) -> Result<()> {
+        let path = persist::state_path(&self.info.name).ok_or(Error::State(String::from("no config directory")))?;
+        self.persist_to(path);
+        Ok(())
+    }
+
+    /// Like [`persist`](Self::persist), with the state saved at `path`
+    pub fn persist_to(&mut self, path: impl Into<PathBuf>) {
+        let registered = self.state_path.is_some();
+        self.state_path = Some(path.into());
+        self.load_state();
+        if !registered {
+            self.on_end(| ext | ext.save_state_or_warn());
+            self.on_socket_disconnect(| ext | ext.save_state_or_warn());
+        }
+    }
+
+    /// Saves the globals now, does nothing if the extension isn't persisted
+    pub fn save_state(&self) -> Result<()> {
+        match &self.state_path {
+            Some(path) => persist::save(path, &self.globals),
+            None => Ok(())
+        }
+    }
+
+    /// Replaces the globals by the saved ones, keeps them if nothing was saved yet
+    fn load_state(&mut self) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+        match persist::load(path) {
+            Ok(Some(globals)) => self.globals = globals,
+            Ok(None) => {},
+            Err(e) => {
+                self.globals = W::default();
+                let _ = self.handle.write_to_console_buffered(format!("{e}, using default globals"), ConsoleColour::Orange);
+            }
+        }
+    }
+
+    fn save_state_or_warn(&mut self) {
+        if let Err(e) = self.save_state() {
+            let _ = self.handle.write_to_console_buffered(e.to_string(), ConsoleColour::Red);
+        }
+    }
+}
+
+impl <W: Debug + 'static> Extension<W> {
+    pub fn with_globals(globals: W) -> Self {
         Extension {
             info: ExtensionInfo::default(),
-            connection: None,
-            globals: W::default(),
+            capabilities: Capabilities::default(),
+            handle: ExtensionHandle::default(),
+            globals,
             args: env::args().collect(),
-            packet_info_manager: None,
-
+            port_source: PortSource::default(),
+            arguments: Vec::new(),
+            reconnect_policy: None,
+            #[cfg(feature = "persist")]
+            state_path: None,
+            recorder: None,
+
+            connected_before: false,
             delayed_init: false,
             host_info: None,
 
@@ -98,260 +275,425 @@ impl <W: Debug + Default + 'static> Extension<W> {
             on_end: Vec::new(),
             on_click: Vec::new(),
             on_host_info_update: Vec::new(),
+            on_socket_connect: Vec::new(),
             on_socket_disconnect: Vec::new(),
 
-            intercepts_by_id: HashMap::new(),
-            intercepts_by_name: HashMap::new(),
+            intercepts: InterceptRegistry::default(),
+            commands: CommandRegistry::default(),
+
+            flag_callback: None,
 
-            flag_callback: None
+            scheduler: Scheduler::default(),
+            send_queue: SendQueue::default(),
+            packet_receiver: None
         }
     }
 
-    fn get_argument(&mut self, flags: [&str; 2]) -> String {
-        for i in 0..self.args.len() - 1 {
-            for flag in flags {
-                if self.args[i].to_lowercase() == flag.to_lowercase() {
-                    return self.args[i + 1].clone();
-                }
+    /// Connects to G-Earth and handles its messages until the connection closes
+    ///
+    /// Returns `Ok` when G-Earth closed the connection and an error when connecting, reading or writing failed.
+    /// With a [`reconnect_policy`](Self::reconnect_policy) it reconnects instead, until the policy gives up.
+    /// Errors that aren't [connection errors](Error::is_connection_error), like invalid arguments, are returned right away.
+    pub fn run(&mut self) -> Result<()> {
+        if self.handle.is_connected() {
+            return Ok(());
+        }
+
+        let mut attempt = 0;
+        loop {
+            let res = match self.port().and_then(| port | transport::connect_tcp(&port.to_string())) {
+                Ok(stream) => {
+                    attempt = 0;
+                    self.run_with_transport(stream)
+                },
+                Err(e) => Err(e)
+            };
+            if res.as_ref().is_err_and(| e | !e.is_connection_error()) {
+                return res;
             }
+            let Some(delay) = self.reconnect_policy.as_ref().and_then(| policy | policy.delay(attempt)) else {
+                return res;
+            };
+            thread::sleep(delay);
+            attempt += 1;
         }
+    }
 
-        String::new()
+    fn port(&self) -> Result<u16> {
+        self.port_source.port(&self.args, &self.arguments)
     }
 
-    pub fn run(&mut self) {
-        if self.connection.is_none() {
-            self.connection = Some(GEarthConnection::new(self.get_argument(PORT_FLAG)));
-            //let read_handle = thread::spawn(move || {
-            self.read_loop();
-            trigger_listeners!(self.on_socket_disconnect, self);
-            //});
+    /// Declares an argument the extension can be started with, read it with [`arguments`](Self::arguments)
+    pub fn add_argument(&mut self, argument: Argument) {
+        self.arguments.push(argument);
+    }
 
-            //read_handle.join().unwrap();
+    /// Parses [`args`](Self::args) for G-Earth's arguments and the ones added with [`add_argument`](Self::add_argument)
+    pub fn arguments(&self) -> Result<Arguments> {
+        Arguments::parse(&self.args, &self.arguments)
+    }
+
+    /// Same as [`run`](Self::run), but talks to G-Earth over `transport` instead of connecting to the port in the arguments
+    pub fn run_with_transport(&mut self, transport: impl Transport + 'static) -> Result<()> {
+        if self.handle.is_connected() {
+            return Ok(());
         }
+
+        let connection = GEarthConnection::new(Box::new(transport))?;
+        self.handle.set_name(&self.info.name);
+        self.handle.set_writer(Some(connection.writer()));
+        let kind = if self.connected_before { ConnectionKind::Resumed } else { ConnectionKind::New };
+        self.connected_before = true;
+        trigger_listeners!(self.on_socket_connect, kind);
+
+        let res = self.read_loop(connection);
+        self.handle.set_writer(None);
+        self.handle.clear_response_waiters();
+        let _ = self.handle.set_initialized(false);
+        self.reset_connection_state();
+        self.flag_callback = None;
+        trigger_listeners!(self.on_socket_disconnect);
+
+        match res {
+            Err(Error::Disconnected) => Ok(()),
+            res => res
+        }
+    }
+
+    /// Returns a thread-safe handle that can be used to interact with G-Earth from other threads
+    pub fn handle(&self) -> ExtensionHandle {
+        self.handle.set_name(&self.info.name);
+        self.handle.clone()
     }
 
     pub fn get_packet_info_manager(&self) -> Option<PacketInfoManager> {
-        return self.packet_info_manager.clone()
+        self.handle.get_packet_info_manager()
     }
 
     pub fn get_host_info(&self) -> Option<HostInfo> {
         return self.host_info.clone()
     }
 
-    fn read_loop(&mut self) {
-        loop {
-            let length_bytes: Result<Vec<u8>, Error> = self.connection.clone().unwrap().read(4);
-            if length_bytes.is_err() {
-                break;
+    /// Records every intercepted message to `recorder` from now on, together with the packet info of the connection
+    pub fn start_recording(&mut self, mut recorder: Recorder) -> Result<()> {
+        if let Some(packet_info_manager) = self.handle.get_packet_info_manager() {
+            recorder.record_packet_info(&packet_info_manager)?;
+        }
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Stops recording and returns the recorder, flushed
+    pub fn stop_recording(&mut self) -> Result<Option<Recorder>> {
+        let mut recorder = self.recorder.take();
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.flush()?;
+        }
+        Ok(recorder)
+    }
+
+    fn read_loop(&mut self, mut connection: GEarthConnection) -> Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                let packet = connection.read_packet();
+                let failed = packet.is_err();
+                if sender.send(packet).is_err() || failed {
+                    break;
+                }
             }
-            let length = i32::from_packet(length_bytes.clone().unwrap()).0;
-            let body_bytes: Result<Vec<u8>, Error> = self.connection.clone().unwrap().read(length as u64);
-            if body_bytes.is_err() {
-                break;
+        });
+
+        self.packet_receiver = Some(receiver);
+        let res = loop {
+            let timeout = self.time_until_next();
+            if let Err(e) = self.dispatch_next(timeout) {
+                break Err(e);
             }
-            let mut bytes = length_bytes.unwrap();
-            bytes.append(&mut body_bytes.unwrap());
-            self.on_g_packet(HPacket::from_bytes(bytes));
+        };
+        self.packet_receiver = None;
+        res
+    }
+
+    /// Waits at most `timeout` for the next packet from G-Earth, handles it and runs all due tasks
+    fn dispatch_next(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let packet = match (&self.packet_receiver, timeout) {
+            (Some(receiver), Some(timeout)) => receiver.recv_timeout(timeout),
+            (Some(receiver), None) => receiver.recv().map_err(| _ | RecvTimeoutError::Disconnected),
+            (None, _) => Err(RecvTimeoutError::Disconnected)
+        };
+        match packet {
+            Ok(packet) => self.on_g_packet(packet?)?,
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => return Err(Error::Disconnected)
         }
+        Scheduler::run_due(self, | ext | &mut ext.scheduler);
+        SendQueue::run_due(self, | ext | &mut ext.send_queue);
+        Ok(())
     }
 
-    fn on_g_packet(&mut self, mut packet: HPacket) {
-        match packet.header_id() {
-            IncomingMessageIds::INFO_REQUEST => self.on_info_request_packet(),
-            IncomingMessageIds::CONNECTION_START => self.on_connection_start_packet(packet),
+    /// Time until the next scheduled task or queued packet is due
+    fn time_until_next(&mut self) -> Option<Duration> {
+        match (self.scheduler.time_until_next(), self.send_queue.time_until_next()) {
+            (Some(task), Some(packet)) => Some(task.min(packet)),
+            (task, packet) => task.or(packet)
+        }
+    }
+
+    fn on_g_packet(&mut self, packet: HPacket) -> Result<()> {
+        self.handle.set_name(&self.info.name);
+        let header_id = packet.header_id();
+        match header_id {
+            IncomingMessageIds::INFO_REQUEST => self.on_info_request_packet()?,
+            IncomingMessageIds::CONNECTION_START => self.on_connection_start_packet(packet)?,
             IncomingMessageIds::CONNECTION_END => self.on_connection_end_packet(),
-            IncomingMessageIds::FLAGS_CHECK => self.on_flags_check_packet(packet),
-            IncomingMessageIds::INIT => self.on_init_packet(packet),
+            IncomingMessageIds::FLAGS_CHECK => self.on_flags_check_packet(packet)?,
+            IncomingMessageIds::INIT => self.on_init_packet(packet)?,
             IncomingMessageIds::ON_DOUBLE_CLICK => self.on_double_click_packet(),
-            IncomingMessageIds::PACKET_INTERCEPT => self.on_packet_intercept_packet(packet),
-            IncomingMessageIds::UPDATE_HOST_INFO => self.on_update_host_info_packet(packet),
-            _ => println!("Unknown incoming message")
+            IncomingMessageIds::PACKET_INTERCEPT => self.on_packet_intercept_packet(packet)?,
+            IncomingMessageIds::UPDATE_HOST_INFO => self.on_update_host_info_packet(packet)?,
+            IncomingMessageIds::PACKET_TO_STRING_RESPONSE | IncomingMessageIds::STRING_TO_PACKET_RESPONSE => self.handle.resolve_conversion(packet),
+            _ => self.handle.report_error(format!("Unknown incoming message {header_id}"))
         }
+        Ok(())
     }
 
-    fn on_info_request_packet(&mut self) {
-        let file = self.get_argument(FILE_FLAG);
-        let cookie = self.get_argument(COOKIE_FLAG);
+    fn on_info_request_packet(&mut self) -> Result<()> {
+        let arguments = self.arguments()?;
+        let file = arguments.file().unwrap_or_default();
+        let cookie = arguments.cookie().unwrap_or_default();
         let mut response = HPacket::from_header_id(OutgoingMessageIds::EXTENSION_INFO);
         response.append((
             self.info.name.clone(),
             self.info.author.clone(),
             self.info.version.clone(),
             self.info.description.clone(),
-            !self.on_click.is_empty(), // onclick
+            self.capabilities.clickable.unwrap_or(!self.on_click.is_empty()), // onclick
             file != "", // file == null
             String::from(file), // file
             String::from(cookie), // cookie
-            true, // can leave
-            true // can delete
+            self.capabilities.can_leave, // can leave
+            self.capabilities.can_delete // can delete
         ));
-        self.connection.clone().unwrap().write(response.get_bytes());
+        self.handle.write(response.get_bytes())
     }
 
-    fn on_connection_start_packet(&mut self, mut packet: HPacket) {
-        let connection_info: ConnectionInfo = packet.read();
-        self.packet_info_manager = packet.read();
+    fn on_connection_start_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        let connection_info: ConnectionInfo = packet.try_read_field("connection info")?;
+        self.set_packet_info_manager(packet.try_read_field("packet info")?);
+        if let (Some(recorder), Some(packet_info_manager)) = (self.recorder.as_mut(), self.handle.get_packet_info_manager()) {
+            if let Err(e) = recorder.record_packet_info(&packet_info_manager) {
+                self.stop_recording_with_error(e);
+            }
+        }
         *CUR_CLIENT.lock().unwrap() = connection_info.client.clone();
 
         if self.delayed_init {
-            trigger_listeners!(self.on_init.clone(), self);
+            trigger_listeners!(self.on_init);
             self.delayed_init = false;
         }
 
-        trigger_listeners!(self.on_connect, self, connection_info.clone());
-        trigger_listeners!(self.on_start, self);
+        trigger_listeners!(self.on_connect, connection_info.clone());
+        trigger_listeners!(self.on_start);
+        Ok(())
     }
 
     fn on_connection_end_packet(&mut self) {
-        self.packet_info_manager = None;
-        trigger_listeners!(self.on_end, self);
+        self.reset_connection_state();
+        trigger_listeners!(self.on_end);
+    }
+
+    pub(crate) fn set_packet_info_manager(&mut self, packet_info_manager: Option<PacketInfoManager>) {
+        self.handle.set_packet_info_manager(packet_info_manager);
+        self.intercepts.set_packet_info_manager(self.handle.get_packet_info_manager());
     }
 
-    fn on_flags_check_packet(&mut self, mut packet: HPacket) {
-        if self.flag_callback.is_some() {
-            let count: i32 = packet.read();
+    /// Forgets everything about the game connection, registered listeners and intercepts are kept
+    fn reset_connection_state(&mut self) {
+        self.set_packet_info_manager(None);
+        self.scheduler.cancel_all();
+        SendQueue::clear(self, | ext | &mut ext.send_queue);
+        self.commands.own_id = None;
+        self.commands.own_room_index = None;
+    }
+
+    fn on_flags_check_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        if let Some(callback) = self.flag_callback.take() {
+            let count: i32 = packet.try_read_field("count")?;
             let mut flags: Vec<String> = Vec::new();
-            for _ in 0..count {
-                flags.push(packet.read());
+            for i in 0..count {
+                flags.push(packet.try_read_field(format!("flags[{i}]"))?);
             }
-            (self.flag_callback.unwrap())(self, flags);
+            (callback)(self, flags);
         }
-        self.flag_callback = None;
+        Ok(())
     }
 
-    fn on_init_packet(&mut self, mut packet: HPacket) {
-        (self.delayed_init, self.host_info) = packet.read();
-        trigger_listeners!(self.on_host_info_update, self, self.host_info.clone().unwrap());
+    fn on_init_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        (self.delayed_init, self.host_info) = packet.try_read()?;
+        if let Some(host_info) = self.host_info.clone() {
+            trigger_listeners!(self.on_host_info_update, host_info.clone());
+        }
         if !self.delayed_init {
-            trigger_listeners!(self.on_init, self);
+            trigger_listeners!(self.on_init);
         }
 
-        self.connection.clone().unwrap().write_to_console_formatted(format!("Extension \"{}\" successfully initialized", self.info.name), ConsoleColour::Green);
+        self.handle.write_to_console_unprefixed(format!("Extension \"{}\" successfully initialized", self.info.name), ConsoleColour::Green)?;
+        self.handle.set_initialized(true)
     }
 
     fn on_double_click_packet(&mut self) {
-        trigger_listeners!(self.on_click, self);
+        trigger_listeners!(self.on_click);
     }
 
-    fn on_packet_intercept_packet(&mut self, mut packet: HPacket) {
-        let string_message: LongString = packet.read();
+    fn on_packet_intercept_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        let string_message: LongString = packet.try_read_field("message")?;
         let mut h_message = HMessage::from_string(string_message.clone());
-
-        self.modify_message(&mut h_message);
+        #[cfg(feature = "tracing")]
+        let _span = self.intercept_span(&mut h_message).entered();
+
+        self.handle.resolve_response_waiters(&mut h_message);
+        let original = self.recorder.is_some().then(|| h_message.clone());
+        self.dispatch_intercept(&mut h_message);
+        if let Some(mut original) = original {
+            self.record(&mut original, &mut h_message);
+        }
 
         let mut response_packet = HPacket::from_header_id(OutgoingMessageIds::MANIPULATED_PACKET);
         response_packet.append(LongString(h_message.stringify()));
 
-        self.connection.clone().unwrap().write(response_packet.get_bytes());
+        self.handle.write(response_packet.get_bytes())
     }
 
-    fn modify_message(&mut self, msg: &mut HMessage) {
-        let names_and_hashes: Vec<String> = if self.packet_info_manager.is_some() {
-            let packet_infos = self.packet_info_manager.clone().unwrap()
-                .get_all_packet_info_from_header_id(
-                    msg.get_destination(),
-                    msg.get_packet().header_id() as i32
-                );
-            packet_infos.iter()
-                .flat_map(| packet_info | vec![packet_info.name.clone(), packet_info.hash.clone()])
-                .filter(| n | n != "NULL")
-                .collect()
-        } else { Vec::new() };
-
-        let mut matching_listeners_by_id: Vec<Box<dyn Fn(&mut Self, &mut HMessage) + Send + Sync>> = Vec::new();
+    #[cfg(feature = "tracing")]
+    fn intercept_span(&mut self, msg: &mut HMessage) -> tracing::Span {
+        let direction = msg.get_destination();
         let header_id = msg.get_packet().header_id();
-        let intercepts_by_id = self.intercepts_by_id.get_mut(&msg.get_destination());
-        if intercepts_by_id.is_some() {
-            for (id, listeners) in intercepts_by_id.unwrap() {
-                if *id == msg.get_packet().header_id() {
-                    matching_listeners_by_id.append(listeners)
-                }
+        let span = tracing::info_span!(INTERCEPT_SPAN, direction = %direction.to_string(), index = msg.get_index(), header_id, packet = tracing::field::Empty);
+        if !span.is_disabled() {
+            if let Some(name) = self.intercepts.packet_name(&direction, header_id) {
+                span.record("packet", name.as_str());
             }
         }
-
-        for listener in matching_listeners_by_id {
-            (listener)(self, msg);
-            self.intercept_raw(msg.get_destination(), header_id as i32, listener);
+        span
+    }
+
+    fn record(&mut self, original: &mut HMessage, msg: &mut HMessage) {
+        let direction = original.get_destination();
+        let header_id = original.get_packet().header_id();
+        let captured = CapturedMessage {
+            timestamp: SystemTime::now(),
+            index: original.get_index(),
+            direction: direction.clone(),
+            header_id,
+            name: self.intercepts.packet_name(&direction, header_id),
+            packet: original.get_packet().clone(),
+            blocked: msg.blocked,
+            modified: msg.get_packet().bytes() != original.get_packet().bytes()
+        };
+        if let Some(Err(e)) = self.recorder.as_mut().map(| recorder | recorder.record(&captured)) {
+            self.stop_recording_with_error(e);
         }
+    }
 
-        let mut matching_listeners_by_name: Vec<Box<dyn Fn(&mut Self, &mut HMessage) + Send + Sync>> = Vec::new();
-        let intercepts_by_name = self.intercepts_by_name.get_mut(&msg.get_destination());
-        let mut name = String::default();
-        if intercepts_by_name.is_some() {
-            for (name_or_hash, listeners) in intercepts_by_name.unwrap() {
-                if names_and_hashes.contains(name_or_hash) {
-                    name = name_or_hash.clone();
-                    matching_listeners_by_name.append(listeners)
-                }
+    fn stop_recording_with_error(&mut self, e: Error) {
+        self.recorder = None;
+        self.handle.report_error(format!("Stopped recording: {e}"));
+    }
+
+    /// Passes `msg` to every intercept listener matching it, like when G-Earth intercepts it
+    pub(crate) fn dispatch_intercept(&mut self, msg: &mut HMessage) {
+        let direction = msg.get_destination();
+        let header_id = msg.get_packet().header_id();
+        let Some(handles) = self.intercepts.matching(&direction, header_id) else {
+            return;
+        };
+        for handle in handles.iter().copied() {
+            if let Some(mut listener) = self.intercepts.take(handle) {
+                (listener)(self, msg);
+                self.intercepts.restore(handle, listener);
             }
         }
+    }
 
-        for listener in matching_listeners_by_name {
-            (listener)(self, msg);
-            self.intercept_raw(msg.get_destination(), name.clone(), listener);
+    fn on_update_host_info_packet(&mut self, mut packet: HPacket) -> Result<()> {
+        self.host_info = packet.try_read()?;
+        if let Some(host_info) = self.host_info.clone() {
+            trigger_listeners!(self.on_host_info_update, host_info.clone());
         }
+        Ok(())
     }
 
-    fn on_update_host_info_packet(&mut self, mut packet: HPacket) {
-        self.host_info = packet.read();
-        trigger_listeners!(self.on_host_info_update, self, self.host_info.clone().unwrap());
+    pub fn write_to_console(&self, s: String) -> Result<()> {
+        self.handle().write_to_console(s)
     }
 
-    pub fn write_to_console(&self, s: String) {
-        self.connection.clone().expect("Extension not connected yet...").write_to_console(format!("[{}] {s}", self.info.name));
+    pub fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        self.handle().write_to_console_formatted(s, colour)
     }
 
-    pub fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) {
-        self.connection.clone().expect("Extension not connected yet...").write_to_console_formatted(format!("[{}] {s}", self.info.name), colour);
+    pub fn on_init(&mut self, listener: impl FnMut(&mut Self) + Send + 'static) {
+        self.on_init.push(Box::new(listener));
     }
 
-    pub fn on_init(&mut self, listener: fn(ext: &mut Self)) {
-        self.on_init.push(listener);
+    /// Called when the extension connected to G-Earth, before G-Earth sends anything
+    pub fn on_socket_connect(&mut self, listener: impl FnMut(&mut Self, ConnectionKind) + Send + 'static) {
+        self.on_socket_connect.push(Box::new(listener));
     }
 
-    pub fn on_socket_disconnect(&mut self, listener: fn(ext: &mut Self)) {
-        self.on_socket_disconnect.push(listener);
+    pub fn on_socket_disconnect(&mut self, listener: impl FnMut(&mut Self) + Send + 'static) {
+        self.on_socket_disconnect.push(Box::new(listener));
     }
 
-    pub fn on_connect(&mut self, listener: fn(ext: &mut Self, connection_info: ConnectionInfo)) {
-        self.on_connect.push(listener);
+    pub fn on_connect(&mut self, listener: impl FnMut(&mut Self, ConnectionInfo) + Send + 'static) {
+        self.on_connect.push(Box::new(listener));
     }
 
-    pub fn on_start(&mut self, listener: fn(ext: &mut Self)) {
-        self.on_start.push(listener);
+    pub fn on_start(&mut self, listener: impl FnMut(&mut Self) + Send + 'static) {
+        self.on_start.push(Box::new(listener));
     }
 
-    pub fn on_end(&mut self, listener: fn(ext: &mut Self)) {
-        self.on_end.push(listener);
+    pub fn on_end(&mut self, listener: impl FnMut(&mut Self) + Send + 'static) {
+        self.on_end.push(Box::new(listener));
     }
 
-    pub fn on_host_info_update(&mut self, listener: fn(ext: &mut Self, host_info: HostInfo)) {
-        self.on_host_info_update.push(listener);
+    pub fn on_host_info_update(&mut self, listener: impl FnMut(&mut Self, HostInfo) + Send + 'static) {
+        self.on_host_info_update.push(Box::new(listener));
     }
 
-    pub fn on_click(&mut self, listener: fn(ext: &mut Self)) {
-        self.on_click.push(listener);
+    pub fn on_click(&mut self, listener: impl FnMut(&mut Self) + Send + 'static) {
+        self.on_click.push(Box::new(listener));
     }
 
-    pub fn request_flags(&mut self, callback: fn(ext: &mut Self, flags: Vec<String>)) {
-        self.flag_callback = Some(callback);
+    pub fn request_flags(&mut self, callback: impl FnOnce(&mut Self, Vec<String>) + Send + 'static) -> Result<()> {
+        self.flag_callback = Some(Box::new(callback));
         let request_packet = HPacket::from_header_id(OutgoingMessageIds::REQUEST_FLAGS);
-        self.connection.clone().unwrap().write(request_packet.get_bytes());
+        self.handle.write(request_packet.get_bytes())
+    }
+
+    pub fn intercept<T: BaseParser + 'static>(&mut self, listener: impl FnMut(&mut Self, &mut HMessage, &mut T) + Send + 'static) -> InterceptHandle {
+        self.intercept_with_priority(DEFAULT_PRIORITY, listener)
     }
 
-    pub fn intercept<T: BaseParser + 'static>(&mut self, listener: fn(ext: &mut Self, msg: &mut HMessage, object: &mut T)) {
-        self.intercept_raw(T::get_direction(), T::get_packet_name(), Self::wrap_listener(listener));
+    pub fn intercept_with_priority<T: BaseParser + 'static>(&mut self, priority: i32, listener: impl FnMut(&mut Self, &mut HMessage, &mut T) + Send + 'static) -> InterceptHandle {
+        self.intercept_raw_with_priority(T::get_direction(), T::get_packet_name(), priority, Self::wrap_listener(listener))
     }
 
-    pub fn intercept_by_id<T: BaseParser + 'static>(&mut self, id: i32, listener: fn(ext: &mut Self, msg: &mut HMessage, object: &mut T)) {
-        self.intercept_raw(T::get_direction(), id, Self::wrap_listener(listener));
+    pub fn intercept_by_id<T: BaseParser + 'static>(&mut self, id: i32, listener: impl FnMut(&mut Self, &mut HMessage, &mut T) + Send + 'static) -> InterceptHandle {
+        self.intercept_raw(T::get_direction(), id, Self::wrap_listener(listener))
     }
 
-    fn wrap_listener<T: BaseParser + 'static>(listener: fn(ext: &mut Self, msg: &mut HMessage, object: &mut T)) -> impl Fn(&mut Self, &mut HMessage) + 'static {
+    fn wrap_listener<T: BaseParser + 'static>(mut listener: impl FnMut(&mut Self, &mut HMessage, &mut T) + Send + 'static) -> impl FnMut(&mut Self, &mut HMessage) + Send + 'static {
         move | ext: &mut Self, msg: &mut HMessage | {
             let mut original_packet = msg.get_packet().clone();
-            let mut object: T = original_packet.read();
+            let mut object = match T::try_parse(&mut original_packet) {
+                Ok(object) => object,
+                Err(e) => {
+                    // Leave the packet alone so it still reaches the other listeners and its destination
+                    ext.handle.report_error(format!("Couldn't parse {}: {e}", T::get_packet_name()));
+                    return;
+                }
+            };
             let original_object = object.clone();
             (listener)(ext, msg, &mut object);
 
@@ -361,82 +703,258 @@ impl <W: Debug + Default + 'static> Extension<W> {
         }
     }
 
-    pub fn intercept_raw<I: InterceptIndicator>(&mut self, direction: HDirection, indicator: I, listener: impl Fn(&mut Self, &mut HMessage) + Send + Sync + 'static) {
-        let intercepts = if I::is_raw_habbo_header_id() {
-            self.intercepts_by_id
-                .entry(direction)
-                .or_insert_with(|| HashMap::new())
-                .entry(indicator.get_habbo_header_id(HDirection::None, &mut PacketInfoManager::default()).unwrap())
-                .or_insert_with( || Vec::new())
-        } else {
-            self.intercepts_by_name
-                .entry(direction)
-                .or_insert_with(|| HashMap::new())
-                .entry(indicator.get_habbo_header_name())
-                .or_insert_with( || Vec::new())
-        };
+    pub fn intercept_raw<I: InterceptIndicator>(&mut self, direction: HDirection, indicator: I, listener: impl FnMut(&mut Self, &mut HMessage) + Send + 'static) -> InterceptHandle {
+        self.intercept_raw_with_priority(direction, indicator, DEFAULT_PRIORITY, listener)
+    }
+
+    /// Registers an intercept listener
+    ///
+    /// Listeners matching a message are called in order of:
+    /// 1. Higher `priority` first (the methods without a priority use [`DEFAULT_PRIORITY`])
+    /// 2. Listeners registered by header id before listeners registered by name or hash
+    /// 3. Registration order
+    pub fn intercept_raw_with_priority<I: InterceptIndicator>(&mut self, direction: HDirection, indicator: I, priority: i32, listener: impl FnMut(&mut Self, &mut HMessage) + Send + 'static) -> InterceptHandle {
+        self.intercepts.add(direction, InterceptTarget::from_indicator(indicator), priority, Box::new(listener))
+    }
 
-        intercepts.push(Box::new(listener));
+    /// Unregisters an intercept listener, returns `false` if it was already removed
+    ///
+    /// Can be called from inside any listener, including the one being removed
+    pub fn remove_intercept(&mut self, handle: InterceptHandle) -> bool {
+        self.intercepts.remove(handle)
     }
 
-    pub fn send_to_client(&self, packet: HPacket) -> bool {
-        self.send_internal(packet, HDirection::ToClient)
+    /// Registers a chat command, called when the user says, shouts or whispers a message starting with `name`
+    ///
+    /// The message is blocked from reaching the server. The words after the name are parsed into `A`,
+    /// when that fails the user gets the error and the command's usage as [`feedback`](Self::feedback).
+    /// A `:help` command listing all commands is added unless one is registered.
+    pub fn command<A: CommandArgs + 'static>(&mut self, name: impl Into<String>, mut handler: impl FnMut(&mut Self, A) + Send + 'static) -> &mut Command<Self> {
+        if !self.commands.installed {
+            self.install_command_intercepts();
+        }
+        self.commands.add::<A>(name.into(), Box::new(move | ext, args | {
+            (handler)(ext, A::parse(args)?);
+            Ok(())
+        }))
+    }
+
+    fn install_command_intercepts(&mut self) {
+        self.commands.installed = true;
+        self.intercept_with_priority(COMMAND_PRIORITY, | ext: &mut Self, msg: &mut HMessage, chat: &mut outgoing::Chat | {
+            ext.on_command_message(msg, &chat.text);
+        });
+        self.intercept_with_priority(COMMAND_PRIORITY, | ext: &mut Self, msg: &mut HMessage, shout: &mut outgoing::Shout | {
+            ext.on_command_message(msg, &shout.text);
+        });
+        self.intercept_with_priority(COMMAND_PRIORITY, | ext: &mut Self, msg: &mut HMessage, whisper: &mut outgoing::Whisper | {
+            // Whispers are sent as "<recipient> <message>"
+            if let Some((_, text)) = whisper.text.split_once(' ') {
+                ext.on_command_message(msg, text);
+            }
+        });
+        self.intercept(| ext: &mut Self, _msg: &mut HMessage, user: &mut incoming::UserObject | {
+            ext.commands.own_id = Some(user.id);
+        });
+        self.intercept(| ext: &mut Self, _msg: &mut HMessage, users: &mut incoming::Users | {
+            for user in users.users.iter() {
+                if let User::Player { id, room_index, .. } = user {
+                    if Some(*id) == ext.commands.own_id {
+                        ext.commands.own_room_index = Some(*room_index);
+                    }
+                }
+            }
+        });
+    }
+
+    fn on_command_message(&mut self, msg: &mut HMessage, text: &str) {
+        let Some((name, args)) = split_command(text) else {
+            return;
+        };
+
+        if let Some(index) = self.commands.find(name) {
+            msg.blocked = true;
+            if let Some(mut handler) = self.commands.take(index) {
+                let res = (handler)(self, &args);
+                self.commands.restore(index, handler);
+                if let Err(e) = res {
+                    let usage = self.commands.get(index).usage();
+                    self.report_feedback(format!("{e}, usage: {usage}"));
+                }
+            }
+        } else if name.eq_ignore_ascii_case(HELP_COMMAND) {
+            msg.blocked = true;
+            for line in self.commands.help() {
+                self.report_feedback(line);
+            }
+        }
     }
 
-    pub fn send_to_server(&self, packet: HPacket) -> bool {
-        self.send_internal(packet, HDirection::ToServer)
+    /// Shows `text` to the user as a whisper from themselves that only they can see
+    ///
+    /// Falls back to the G-Earth console until the user has been seen entering a room,
+    /// since the whisper needs their room index
+    pub fn feedback(&self, text: impl Into<String>) -> Result<()> {
+        match self.commands.own_room_index {
+            Some(user_index) => self.send(incoming::Whisper {
+                user_index,
+                text: text.into(),
+                ..Default::default()
+            }),
+            None => self.write_to_console(text.into())
+        }
     }
 
-    fn send_internal(&self, mut packet: HPacket, direction: HDirection) -> bool {
-        if packet.is_corrupted() || self.connection.clone().is_none() {
-            return false;
+    fn report_feedback(&self, text: String) {
+        if let Err(e) = self.feedback(text) {
+            self.handle.report_error(format!("Couldn't send command feedback: {e}"));
         }
+    }
 
-        if !packet.is_complete()
-            && self.packet_info_manager.clone().is_some()
-            && packet.can_complete(self.packet_info_manager.clone().unwrap()) {
-            packet.complete_packet(self.packet_info_manager.clone().unwrap());
+    /// Runs the task once on the extension's dispatch thread after the given delay
+    ///
+    /// All scheduled tasks are cancelled when the connection ends or the socket disconnects
+    pub fn schedule_once(&mut self, delay: Duration, task: impl FnOnce(&mut Self) + Send + 'static) -> TaskHandle {
+        let mut task = Some(task);
+        self.scheduler.schedule(delay, None, Box::new(move | ext | {
+            if let Some(task) = task.take() {
+                (task)(ext);
+            }
+        }))
+    }
+
+    /// Runs the task on the extension's dispatch thread every `interval`, starting one interval from now
+    ///
+    /// All scheduled tasks are cancelled when the connection ends or the socket disconnects
+    pub fn schedule_repeating(&mut self, interval: Duration, task: impl FnMut(&mut Self) + Send + 'static) -> TaskHandle {
+        self.scheduler.schedule(interval, Some(interval), Box::new(task))
+    }
+
+    /// Sends `request` and waits until a `Resp` packet matching `predicate` is intercepted
+    ///
+    /// Packets and scheduled tasks keep being dispatched while waiting, except for the listener
+    /// or task that is currently running
+    pub fn request<Req: BaseParser, Resp: BaseParser + 'static>(&mut self, request: Req, predicate: impl Fn(&Resp) -> bool + Send + 'static, timeout: Duration) -> Result<Resp> {
+        let (id, receiver) = self.handle.add_response_waiter(predicate);
+        if let Err(e) = self.send(request) {
+            self.handle.remove_response_waiter(id);
+            return Err(e);
         }
-        if !packet.is_complete() {
-            return false;
+
+        let response = self.wait_for_response(&receiver, timeout);
+        self.handle.remove_response_waiter(id);
+
+        let mut packet = response?;
+        packet.reset_read_index();
+        Ok(Resp::try_parse(&mut packet)?)
+    }
+
+    /// Asks G-Earth for the packet's string representation, as shown in its packet logger
+    ///
+    /// Packets and scheduled tasks keep being dispatched while waiting, like with [`request`](Self::request)
+    pub fn packet_to_string(&mut self, packet: &HPacket) -> Result<String> {
+        let receiver = self.handle.request_conversion(packet_to_string_request(packet))?;
+        let response = self.wait_for_response(&receiver, CONVERSION_TIMEOUT)?;
+        Ok(read_packet_to_string_response(response)?.0)
+    }
+
+    /// Asks G-Earth for the packet's expression, e.g. `{out:Chat}{s:"hi"}{i:0}{i:0}`
+    pub fn packet_to_expression(&mut self, packet: &HPacket) -> Result<String> {
+        let receiver = self.handle.request_conversion(packet_to_string_request(packet))?;
+        let response = self.wait_for_response(&receiver, CONVERSION_TIMEOUT)?;
+        Ok(read_packet_to_string_response(response)?.1)
+    }
+
+    /// Asks G-Earth to build a packet from a string or expression, as copied from its packet logger
+    pub fn string_to_packet(&mut self, s: &str) -> Result<HPacket> {
+        let receiver = self.handle.request_conversion(string_to_packet_request(s))?;
+        let response = self.wait_for_response(&receiver, CONVERSION_TIMEOUT)?;
+        read_string_to_packet_response(response, s)
+    }
+
+    /// Dispatches packets and scheduled tasks until a packet arrives on `receiver`
+    fn wait_for_response(&mut self, receiver: &Receiver<HPacket>, timeout: Duration) -> Result<HPacket> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Ok(packet) = receiver.try_recv() {
+                return Ok(packet);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Timeout);
+            }
+            let wait = match self.time_until_next() {
+                Some(next_task) => next_task.min(deadline - now),
+                None => deadline - now
+            };
+            self.dispatch_next(Some(wait))?;
         }
+    }
+
+    pub fn send_to_client(&self, packet: HPacket) -> Result<()> {
+        self.handle.send_to_client(packet)
+    }
+
+    pub fn send_to_server(&self, packet: HPacket) -> Result<()> {
+        self.handle.send_to_server(packet)
+    }
 
-        let mut sending_packet = HPacket::from_header_id(OutgoingMessageIds::SEND_MESSAGE);
-        sending_packet.append((direction as u8, packet.bytes_length() as i32));
-        sending_packet.append_bytes(packet.get_bytes());
+    pub fn send<B: BaseParser>(&self, packet_object: B) -> Result<()> {
+        self.handle.send(packet_object)
+    }
 
-        self.connection.clone().unwrap().write(sending_packet.get_bytes());
+    pub fn send_with_id<B: BaseParser>(&self, packet_object: B, header_id: i32) -> Result<()> {
+        self.handle.send_with_id(packet_object, header_id)
+    }
+
+    /// Sends the packet once its [rate limit](Self::set_rate_limit) allows it, instead of right away
+    ///
+    /// Chat packets are also held back while the server's flood control mutes the user. Queued packets are
+    /// dropped when the connection ends, the returned handle can cancel the packet before it's sent.
+    pub fn queue<B: BaseParser + Send + 'static>(&mut self, packet_object: B) -> TaskHandle {
+        self.queue_packet(DEFAULT_PRIORITY, packet_object, None)
+    }
 
-        return true;
+    /// Same as [`queue`](Self::queue), packets with a higher priority are sent first and `on_sent` is called
+    /// with the result of sending the packet, or an error if it was dropped or [cancelled](Error::Cancelled)
+    pub fn queue_with_priority<B: BaseParser + Send + 'static>(&mut self, priority: i32, packet_object: B, on_sent: impl FnOnce(&mut Self, Result<()>) + Send + 'static) -> TaskHandle {
+        self.queue_packet(priority, packet_object, Some(Box::new(on_sent)))
     }
 
-    pub fn send<B: BaseParser>(&self, packet_object: B) -> bool {
-        if self.packet_info_manager.is_none() {
-            println!(
-                "Couldn't send {} packet, packet info manager has not yet been initialized",
-                B::get_packet_name()
-            );
-            return false;
+    fn queue_packet<B: BaseParser + Send + 'static>(&mut self, priority: i32, packet_object: B, on_sent: Option<Box<dyn FnOnce(&mut Self, Result<()>) + Send>>) -> TaskHandle {
+        if !self.send_queue.installed {
+            self.install_flood_control_intercept();
         }
+        self.send_queue.push(B::get_packet_name(), priority, Box::new(move | ext: &mut Self | ext.send(packet_object)), on_sent)
+    }
 
-        let packet_info = self.packet_info_manager.clone().unwrap()
-            .get_packet_info_from_name(B::get_direction(), B::get_packet_name());
+    /// Limits how often queued packets called `name` are sent, chat packets have [`DEFAULT_CHAT_RATE_LIMIT`](super::sendqueue::DEFAULT_CHAT_RATE_LIMIT)
+    /// unless set otherwise
+    pub fn set_rate_limit(&mut self, name: impl Into<String>, limit: RateLimit) {
+        if !self.send_queue.installed {
+            self.install_flood_control_intercept();
+        }
+        self.send_queue.set_limit(name.into(), Some(limit));
+    }
 
-        if packet_info.is_none() {
-            println!(
-                "Couldn't send {}, packet info not found",
-                B::get_packet_name()
-            );
-            return false;
+    /// Sends queued packets called `name` as soon as possible
+    pub fn remove_rate_limit(&mut self, name: impl Into<String>) {
+        if !self.send_queue.installed {
+            self.install_flood_control_intercept();
         }
+        self.send_queue.set_limit(name.into(), None);
+    }
 
-        self.send_with_id(packet_object, packet_info.unwrap().header_id)
+    /// Number of packets waiting in the send queue
+    pub fn queued_packets(&self) -> usize {
+        self.send_queue.pending()
     }
 
-    pub fn send_with_id<B: BaseParser>(&self, packet_object: B, header_id: i32) -> bool {
-        let mut packet = HPacket::from_header_id(header_id as i16);
-        packet_object.append_to_packet(&mut packet);
-        self.send_internal(packet, B::get_direction())
+    fn install_flood_control_intercept(&mut self) {
+        self.send_queue.installed = true;
+        self.intercept_with_priority(FLOOD_CONTROL_PRIORITY, | ext: &mut Self, _msg: &mut HMessage, flood_control: &mut incoming::FloodControl | {
+            ext.send_queue.pause_flood_controlled(Duration::from_secs(flood_control.seconds.max(0) as u64));
+        });
     }
 }
 
@@ -0,0 +1,387 @@
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use super::parsers::baseparser::BaseParser;
+use crate::error::{DecodeError, Error, Result};
+use crate::misc::connection::GEarthWriter;
+use crate::misc::consoleformat::ConsoleColour;
+use crate::misc::messages::{IncomingMessageIds, OutgoingMessageIds};
+use crate::protocol::hdirection::HDirection;
+use crate::protocol::hmessage::HMessage;
+use crate::protocol::hpacket::HPacket;
+use crate::protocol::vars::longstring::LongString;
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+
+/// A cloneable, thread-safe handle to a running [`Extension`](super::extension::Extension)
+///
+/// Can be moved to other threads (timers, file watchers, UI threads, ...) to send packets,
+/// write to the G-Earth console and look up packet info while the extension itself is
+/// blocked in [`Extension::run`](super::extension::Extension::run)
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionHandle {
+    name: Arc<RwLock<String>>,
+    writer: Arc<Mutex<Option<GEarthWriter>>>,
+    packet_info_manager: Arc<RwLock<Option<PacketInfoManager>>>,
+    response_waiters: Arc<Mutex<Vec<ResponseWaiter>>>,
+    next_waiter_id: Arc<AtomicU64>,
+    conversion_waiters: Arc<Mutex<ConversionWaiters>>,
+    console_backlog: Arc<Mutex<ConsoleBacklog>>
+}
+
+/// How long to wait for G-Earth to convert a packet to or from a string
+pub const CONVERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Senders waiting for G-Earth's conversion responses, which arrive in the order of the requests
+#[derive(Debug, Default)]
+struct ConversionWaiters {
+    packet_to_string: VecDeque<mpsc::Sender<HPacket>>,
+    string_to_packet: VecDeque<mpsc::Sender<HPacket>>
+}
+
+/// Most lines kept by [`ExtensionHandle::write_to_console_buffered`] before the extension is initialized, older lines are dropped
+pub const MAX_CONSOLE_BACKLOG: usize = 1000;
+
+#[derive(Debug, Default)]
+struct ConsoleBacklog {
+    initialized: bool,
+    lines: VecDeque<(String, ConsoleColour)>
+}
+
+struct ResponseWaiter {
+    id: u64,
+    direction: HDirection,
+    header_ids: Vec<i16>,
+    matcher: Box<dyn FnMut(&mut HPacket) -> bool + Send>,
+    sender: mpsc::Sender<HPacket>
+}
+
+impl Debug for ResponseWaiter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseWaiter")
+            .field("id", &self.id)
+            .field("direction", &self.direction)
+            .field("header_ids", &self.header_ids)
+            .finish()
+    }
+}
+
+impl ExtensionHandle {
+    pub(crate) fn set_name(&self, name: &str) {
+        let mut cur_name = self.name.write().unwrap();
+        if *cur_name != name {
+            *cur_name = name.to_string();
+        }
+    }
+
+    pub(crate) fn set_writer(&self, writer: Option<GEarthWriter>) {
+        *self.writer.lock().unwrap() = writer;
+    }
+
+    pub(crate) fn set_packet_info_manager(&self, packet_info_manager: Option<PacketInfoManager>) {
+        *self.packet_info_manager.write().unwrap() = packet_info_manager;
+    }
+
+    pub(crate) fn write(&self, bytes: Vec<u8>) -> Result<()> {
+        match &*self.writer.lock().unwrap() {
+            Some(writer) => writer.write(&bytes),
+            None => Err(Error::Disconnected)
+        }
+    }
+
+    pub(crate) fn add_response_waiter<Resp: BaseParser + 'static>(&self, predicate: impl Fn(&Resp) -> bool + Send + 'static) -> (u64, mpsc::Receiver<HPacket>) {
+        let (sender, receiver) = mpsc::channel();
+        let id = self.next_waiter_id.fetch_add(1, Ordering::SeqCst);
+        let header_ids = self.get_packet_info_manager()
+            .map(| mut packet_info_manager | packet_info_manager
+                .get_all_packet_info_from_name(Resp::get_direction(), Resp::get_packet_name())
+                .iter()
+                .map(| packet_info | packet_info.header_id as i16)
+                .collect())
+            .unwrap_or_default();
+        self.response_waiters.lock().unwrap().push(ResponseWaiter {
+            id,
+            direction: Resp::get_direction(),
+            header_ids,
+            matcher: Box::new(move | packet: &mut HPacket | {
+                packet.reset_read_index();
+                Resp::try_parse(packet).is_ok_and(| response | predicate(&response))
+            }),
+            sender
+        });
+        (id, receiver)
+    }
+
+    pub(crate) fn remove_response_waiter(&self, id: u64) {
+        self.response_waiters.lock().unwrap().retain(| waiter | waiter.id != id);
+    }
+
+    pub(crate) fn clear_response_waiters(&self) {
+        self.response_waiters.lock().unwrap().clear();
+        *self.conversion_waiters.lock().unwrap() = ConversionWaiters::default();
+    }
+
+    /// Sends a `PACKET_TO_STRING_REQUEST` or `STRING_TO_PACKET_REQUEST`, the response is sent to the returned receiver
+    pub(crate) fn request_conversion(&self, request: HPacket) -> Result<mpsc::Receiver<HPacket>> {
+        let (sender, receiver) = mpsc::channel();
+        // Keep the lock while writing, so concurrent requests are queued in the order G-Earth receives them
+        let mut waiters = self.conversion_waiters.lock().unwrap();
+        self.write(request.get_bytes())?;
+        if request.header_id() == OutgoingMessageIds::PACKET_TO_STRING_REQUEST {
+            waiters.packet_to_string.push_back(sender);
+        } else {
+            waiters.string_to_packet.push_back(sender);
+        }
+        Ok(receiver)
+    }
+
+    /// Hands a `PACKET_TO_STRING_RESPONSE` or `STRING_TO_PACKET_RESPONSE` to the oldest request waiting for it
+    pub(crate) fn resolve_conversion(&self, response: HPacket) {
+        let mut waiters = self.conversion_waiters.lock().unwrap();
+        let waiter = if response.header_id() == IncomingMessageIds::PACKET_TO_STRING_RESPONSE {
+            waiters.packet_to_string.pop_front()
+        } else {
+            waiters.string_to_packet.pop_front()
+        };
+        if let Some(sender) = waiter {
+            let _ = sender.send(response);
+        }
+    }
+
+    /// Hands the message to every waiting request it answers
+    pub(crate) fn resolve_response_waiters(&self, msg: &mut HMessage) {
+        let direction = msg.get_destination();
+        let header_id = msg.get_packet().header_id();
+        self.response_waiters.lock().unwrap().retain_mut(| waiter | {
+            if waiter.direction != direction || !waiter.header_ids.contains(&header_id) {
+                return true;
+            }
+            let mut packet = msg.get_packet().clone();
+            if (waiter.matcher)(&mut packet) {
+                let _ = waiter.sender.send(packet);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.writer.lock().unwrap().is_some()
+    }
+
+    pub fn get_packet_info_manager(&self) -> Option<PacketInfoManager> {
+        self.packet_info_manager.read().unwrap().clone()
+    }
+
+    pub(crate) fn write_to_console_unprefixed(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        match &*self.writer.lock().unwrap() {
+            Some(writer) => writer.write_to_console_formatted(s, colour),
+            None => Err(Error::Disconnected)
+        }
+    }
+
+    pub fn write_to_console(&self, s: String) -> Result<()> {
+        let name = self.name.read().unwrap().clone();
+        match &*self.writer.lock().unwrap() {
+            Some(writer) => writer.write_to_console(format!("[{name}] {s}")),
+            None => Err(Error::Disconnected)
+        }
+    }
+
+    pub fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        let name = self.name.read().unwrap().clone();
+        self.write_to_console_unprefixed(format!("[{name}] {s}"), colour)
+    }
+
+    /// Writes to the console like [`write_to_console_formatted`](Self::write_to_console_formatted),
+    /// but keeps the line until G-Earth initialized the extension instead of failing
+    pub fn write_to_console_buffered(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        let mut backlog = self.console_backlog.lock().unwrap();
+        if backlog.initialized {
+            return self.write_to_console_formatted(s, colour);
+        }
+
+        if backlog.lines.len() >= MAX_CONSOLE_BACKLOG {
+            backlog.lines.pop_front();
+        }
+        backlog.lines.push_back((s, colour));
+        Ok(())
+    }
+
+    /// Writes an error to the console in red like [`write_to_console_buffered`](Self::write_to_console_buffered),
+    /// or logs it with [`log::error!`] when that fails
+    pub(crate) fn report_error(&self, s: String) {
+        if let Err(e) = self.write_to_console_buffered(s.clone(), ConsoleColour::Red) {
+            log::error!("{s} ({e})");
+        }
+    }
+
+    /// Writes the lines buffered by [`write_to_console_buffered`](Self::write_to_console_buffered) once initialized
+    pub(crate) fn set_initialized(&self, initialized: bool) -> Result<()> {
+        let mut backlog = self.console_backlog.lock().unwrap();
+        backlog.initialized = initialized;
+        if initialized {
+            while let Some((s, colour)) = backlog.lines.pop_front() {
+                self.write_to_console_formatted(s, colour)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn send_to_client(&self, packet: HPacket) -> Result<()> {
+        self.send_internal(packet, HDirection::ToClient)
+    }
+
+    pub fn send_to_server(&self, packet: HPacket) -> Result<()> {
+        self.send_internal(packet, HDirection::ToServer)
+    }
+
+    fn send_internal(&self, packet: HPacket, direction: HDirection) -> Result<()> {
+        if !self.is_connected() {
+            return Err(Error::Disconnected);
+        }
+
+        let sending_packet = wrap_send_packet(packet, direction, self.get_packet_info_manager())?;
+        self.write(sending_packet.get_bytes())
+    }
+
+    pub fn send<B: BaseParser>(&self, packet_object: B) -> Result<()> {
+        let header_id = resolve_header_id::<B>(self.get_packet_info_manager())?;
+        self.send_with_id(packet_object, header_id)
+    }
+
+    pub fn send_with_id<B: BaseParser>(&self, packet_object: B, header_id: i32) -> Result<()> {
+        let mut packet = HPacket::from_header_id(header_id as i16);
+        packet_object.append_to_packet(&mut packet);
+        self.send_internal(packet, B::get_direction())
+    }
+
+    /// Sends `request` and blocks until a `Resp` packet matching `predicate` is intercepted
+    ///
+    /// Must not be called from the extension's dispatch thread (inside listeners or scheduled tasks),
+    /// use [`Extension::request`](super::extension::Extension::request) there instead
+    pub fn request<Req: BaseParser, Resp: BaseParser + 'static>(&self, request: Req, predicate: impl Fn(&Resp) -> bool + Send + 'static, timeout: Duration) -> Result<Resp> {
+        let (id, receiver) = self.add_response_waiter(predicate);
+        if let Err(e) = self.send(request) {
+            self.remove_response_waiter(id);
+            return Err(e);
+        }
+
+        let response = receive_response(&receiver, timeout);
+        self.remove_response_waiter(id);
+        let mut packet = response?;
+        packet.reset_read_index();
+        Ok(Resp::try_parse(&mut packet)?)
+    }
+
+    /// Asks G-Earth for the packet's string representation, as shown in its packet logger
+    ///
+    /// Must not be called from the extension's dispatch thread,
+    /// use [`Extension::packet_to_string`](super::extension::Extension::packet_to_string) there instead
+    pub fn packet_to_string(&self, packet: &HPacket) -> Result<String> {
+        let receiver = self.request_conversion(packet_to_string_request(packet))?;
+        Ok(read_packet_to_string_response(receive_response(&receiver, CONVERSION_TIMEOUT)?)?.0)
+    }
+
+    /// Asks G-Earth for the packet's expression, e.g. `{out:Chat}{s:"hi"}{i:0}{i:0}`
+    ///
+    /// Must not be called from the extension's dispatch thread,
+    /// use [`Extension::packet_to_expression`](super::extension::Extension::packet_to_expression) there instead
+    pub fn packet_to_expression(&self, packet: &HPacket) -> Result<String> {
+        let receiver = self.request_conversion(packet_to_string_request(packet))?;
+        Ok(read_packet_to_string_response(receive_response(&receiver, CONVERSION_TIMEOUT)?)?.1)
+    }
+
+    /// Asks G-Earth to build a packet from a string or expression, as copied from its packet logger
+    ///
+    /// Must not be called from the extension's dispatch thread,
+    /// use [`Extension::string_to_packet`](super::extension::Extension::string_to_packet) there instead
+    pub fn string_to_packet(&self, s: &str) -> Result<HPacket> {
+        let receiver = self.request_conversion(string_to_packet_request(s))?;
+        read_string_to_packet_response(receive_response(&receiver, CONVERSION_TIMEOUT)?, s)
+    }
+}
+
+fn receive_response(receiver: &mpsc::Receiver<HPacket>, timeout: Duration) -> Result<HPacket> {
+    receiver.recv_timeout(timeout).map_err(| e | match e {
+        mpsc::RecvTimeoutError::Timeout => Error::Timeout,
+        mpsc::RecvTimeoutError::Disconnected => Error::Disconnected
+    })
+}
+
+pub(crate) fn packet_to_string_request(packet: &HPacket) -> HPacket {
+    let mut request = HPacket::from_header_id(OutgoingMessageIds::PACKET_TO_STRING_REQUEST);
+    request.append(LongString(packet.stringify()));
+    request
+}
+
+pub(crate) fn string_to_packet_request(s: &str) -> HPacket {
+    let mut request = HPacket::from_header_id(OutgoingMessageIds::STRING_TO_PACKET_REQUEST);
+    // G-Earth reads this one as UTF-8 instead of ISO-8859-1
+    request.append(s.len() as u32);
+    request.append_bytes(s.as_bytes().to_vec());
+    request
+}
+
+/// Reads the string and the expression from a `PACKET_TO_STRING_RESPONSE`
+pub(crate) fn read_packet_to_string_response(mut response: HPacket) -> Result<(String, String)> {
+    response.reset_read_index();
+    let string: LongString = response.try_read_field("string")?;
+    let length: u32 = response.try_read_field("expression")?;
+    if response.get_bytes_available() < length as usize {
+        return Err(Error::Decode(DecodeError::new(response.read_index - 6, "expression longer than the response").in_field("expression")));
+    }
+    let expression = String::from_utf8_lossy(response.read_slice(length as usize)).to_string();
+    Ok((string.0, expression))
+}
+
+pub(crate) fn read_string_to_packet_response(mut response: HPacket, s: &str) -> Result<HPacket> {
+    response.reset_read_index();
+    let stringified: LongString = response.try_read()?;
+    if !stringified.is_empty() {
+        let mut packet = HPacket::from_string(stringified.0);
+        if !packet.is_corrupted() {
+            return Ok(packet);
+        }
+    }
+    Err(Error::Encoding(format!("G-Earth couldn't convert \"{s}\" to a packet")))
+}
+
+/// Completes the packet if needed and wraps it in a G-Earth `SEND_MESSAGE` packet
+pub(crate) fn wrap_send_packet(mut packet: HPacket, direction: HDirection, packet_info_manager: Option<PacketInfoManager>) -> Result<HPacket> {
+    if packet.is_corrupted() {
+        return Err(Error::Encoding("packet is corrupted".to_string()));
+    }
+
+    if !packet.is_complete() {
+        // Without packet info (no game connection yet) the name or hash can't be resolved either
+        match packet_info_manager {
+            Some(packet_info_manager) if packet.can_complete(packet_info_manager.clone()) => packet.complete_packet(packet_info_manager),
+            _ => return Err(Error::UnknownPacket {
+                direction: packet.identifier_direction.clone(),
+                name: packet.identifier.clone()
+            })
+        }
+    }
+
+    let mut sending_packet = HPacket::from_header_id(OutgoingMessageIds::SEND_MESSAGE);
+    sending_packet.append((direction as u8, packet.bytes_length() as i32));
+    sending_packet.append_bytes(packet.get_bytes());
+    Ok(sending_packet)
+}
+
+pub(crate) fn resolve_header_id<B: BaseParser>(packet_info_manager: Option<PacketInfoManager>) -> Result<i32> {
+    let Some(mut packet_info_manager) = packet_info_manager else {
+        return Err(Error::Disconnected);
+    };
+
+    packet_info_manager
+        .get_packet_info_from_name(B::get_direction(), B::get_packet_name())
+        .map(| packet_info | packet_info.header_id)
+        .ok_or_else(|| Error::UnknownPacket {
+            direction: B::get_direction(),
+            name: B::get_packet_name()
+        })
+}
@@ -0,0 +1,191 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::extension::InterceptIndicator;
+use crate::protocol::hdirection::HDirection;
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+
+/// Priority used by the intercept methods that don't take one
+pub const DEFAULT_PRIORITY: i32 = 0;
+
+/// Identifies a registered intercept listener, pass it to `remove_intercept` to unregister it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InterceptHandle(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InterceptTarget {
+    HeaderId(i16),
+    Name(String)
+}
+
+impl InterceptTarget {
+    pub fn from_indicator<I: InterceptIndicator>(indicator: I) -> Self {
+        if I::is_raw_habbo_header_id() {
+            InterceptTarget::HeaderId(indicator.get_habbo_header_id(HDirection::None, &mut PacketInfoManager::default()).unwrap())
+        } else {
+            InterceptTarget::Name(indicator.get_habbo_header_name())
+        }
+    }
+
+    fn order(&self) -> u8 {
+        match self {
+            InterceptTarget::HeaderId(_) => 0,
+            InterceptTarget::Name(_) => 1
+        }
+    }
+}
+
+struct RegisteredIntercept<L> {
+    handle: InterceptHandle,
+    direction: HDirection,
+    target: InterceptTarget,
+    priority: i32,
+    /// Header ids the target resolved to with the current packet info manager
+    header_ids: Vec<i16>,
+    /// `None` while the listener is running
+    listener: Option<L>
+}
+
+impl <L> RegisteredIntercept<L> {
+    fn sort_key(&self) -> (Reverse<i32>, u8, u64) {
+        (Reverse(self.priority), self.target.order(), self.handle.0)
+    }
+
+    /// Header ids this listener should be called for, names and hashes are resolved with the packet info manager
+    fn resolve(&self, packet_info_manager: &mut Option<PacketInfoManager>) -> Vec<i16> {
+        match (&self.target, packet_info_manager) {
+            (InterceptTarget::HeaderId(id), _) => vec![*id],
+            (InterceptTarget::Name(name), _) if name == "NULL" => Vec::new(),
+            (InterceptTarget::Name(name), Some(packet_info_manager)) => {
+                let mut packet_infos = packet_info_manager.get_all_packet_info_from_name(self.direction.clone(), name.clone());
+                packet_infos.append(&mut packet_info_manager.get_all_packet_info_from_hash(self.direction.clone(), name.clone()));
+                let mut header_ids: Vec<i16> = packet_infos.iter()
+                    .map(| packet_info | packet_info.header_id as i16)
+                    .collect();
+                header_ids.sort();
+                header_ids.dedup();
+                header_ids
+            },
+            (InterceptTarget::Name(_), None) => Vec::new()
+        }
+    }
+}
+
+/// Keeps intercept listeners in calling order:
+/// 1. Higher priority first
+/// 2. Listeners registered by header id before listeners registered by name or hash
+/// 3. Registration order
+///
+/// Name and hash based listeners are resolved to header ids when they're added and when the packet info manager
+/// changes. Adding or removing a listener only updates the table entries of its header ids,
+/// so dispatching a message is a single table lookup
+pub(crate) struct InterceptRegistry<L> {
+    intercepts: HashMap<InterceptHandle, RegisteredIntercept<L>>,
+    next_id: u64,
+    packet_info_manager: Option<PacketInfoManager>,
+    dispatch_table: HashMap<(HDirection, i16), Arc<[InterceptHandle]>>
+}
+
+impl <L> Default for InterceptRegistry<L> {
+    fn default() -> Self {
+        Self {
+            intercepts: HashMap::new(),
+            next_id: 0,
+            packet_info_manager: None,
+            dispatch_table: HashMap::new()
+        }
+    }
+}
+
+impl <L> InterceptRegistry<L> {
+    pub fn add(&mut self, direction: HDirection, target: InterceptTarget, priority: i32, listener: L) -> InterceptHandle {
+        let handle = InterceptHandle(self.next_id);
+        self.next_id += 1;
+
+        let mut intercept = RegisteredIntercept {
+            handle,
+            direction,
+            target,
+            priority,
+            header_ids: Vec::new(),
+            listener: Some(listener)
+        };
+        intercept.header_ids = intercept.resolve(&mut self.packet_info_manager);
+
+        let key = intercept.sort_key();
+        for header_id in intercept.header_ids.iter() {
+            let handles = self.dispatch_table.entry((intercept.direction.clone(), *header_id)).or_insert_with(|| Arc::new([]));
+            let index = handles.partition_point(| h | self.intercepts[h].sort_key() <= key);
+            let mut updated = handles.to_vec();
+            updated.insert(index, handle);
+            *handles = updated.into();
+        }
+        self.intercepts.insert(handle, intercept);
+        handle
+    }
+
+    pub fn remove(&mut self, handle: InterceptHandle) -> bool {
+        let Some(intercept) = self.intercepts.remove(&handle) else {
+            return false;
+        };
+        for header_id in intercept.header_ids {
+            let key = (intercept.direction.clone(), header_id);
+            let Some(handles) = self.dispatch_table.get_mut(&key) else {
+                continue;
+            };
+            let remaining: Vec<InterceptHandle> = handles.iter().copied().filter(| h | *h != handle).collect();
+            if remaining.is_empty() {
+                self.dispatch_table.remove(&key);
+            } else {
+                *handles = remaining.into();
+            }
+        }
+        true
+    }
+
+    /// Replaces the packet info manager used to resolve names and hashes, `None` when disconnected
+    ///
+    /// Every listener is resolved again, so the dispatch table is rebuilt from scratch
+    pub fn set_packet_info_manager(&mut self, packet_info_manager: Option<PacketInfoManager>) {
+        self.packet_info_manager = packet_info_manager;
+
+        let mut intercepts: Vec<&mut RegisteredIntercept<L>> = self.intercepts.values_mut().collect();
+        intercepts.sort_by_key(| intercept | intercept.sort_key());
+        let mut dispatch_table: HashMap<(HDirection, i16), Vec<InterceptHandle>> = HashMap::new();
+        for intercept in intercepts {
+            intercept.header_ids = intercept.resolve(&mut self.packet_info_manager);
+            for header_id in intercept.header_ids.iter() {
+                dispatch_table.entry((intercept.direction.clone(), *header_id))
+                    .or_default()
+                    .push(intercept.handle);
+            }
+        }
+        self.dispatch_table = dispatch_table.into_iter()
+            .map(| (key, handles) | (key, handles.into()))
+            .collect();
+    }
+
+    /// Listeners for the given message, in calling order
+    pub fn matching(&self, direction: &HDirection, header_id: i16) -> Option<Arc<[InterceptHandle]>> {
+        self.dispatch_table.get(&(direction.clone(), header_id)).cloned()
+    }
+
+    /// Takes the listener out so it can be called while the registry is borrowed mutably elsewhere
+    pub fn take(&mut self, handle: InterceptHandle) -> Option<L> {
+        self.intercepts.get_mut(&handle)?.listener.take()
+    }
+
+    /// Name of the packet with the given header id, if the packet info manager knows it
+    pub fn packet_name(&mut self, direction: &HDirection, header_id: i16) -> Option<String> {
+        self.packet_info_manager.as_mut()?
+            .get_packet_info_from_header_id(direction.clone(), header_id as i32)
+            .map(| packet_info | packet_info.name)
+    }
+
+    /// Puts a taken listener back, unless it was removed in the meantime
+    pub fn restore(&mut self, handle: InterceptHandle, listener: L) {
+        if let Some(intercept) = self.intercepts.get_mut(&handle) {
+            intercept.listener = Some(listener);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use super::extensionhandle::ExtensionHandle;
+use crate::misc::consoleformat::ConsoleColour;
+
+/// [`log`] backend writing to the G-Earth extension console
+///
+/// Lines are prefixed with the extension name and coloured by level. Lines logged before G-Earth initialized
+/// the extension are buffered and written once it is, see [`ExtensionHandle::write_to_console_buffered`]
+///
+/// ```no_run
+/// # use g_rust::extension::extension::Extension;
+/// # use g_rust::extension::logger::GEarthLogger;
+/// let mut ext: Extension<()> = Extension::new();
+/// GEarthLogger::new(ext.handle()).init().unwrap();
+/// log::info!("Started");
+/// ext.run().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct GEarthLogger {
+    handle: ExtensionHandle,
+    level: LevelFilter
+}
+
+impl GEarthLogger {
+    /// Logs everything from [`Level::Info`] up
+    pub fn new(handle: ExtensionHandle) -> Self {
+        Self {
+            handle,
+            level: LevelFilter::Info
+        }
+    }
+
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets this logger as the global logger of the `log` facade
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+
+    pub fn colour(level: Level) -> ConsoleColour {
+        match level {
+            Level::Error => ConsoleColour::Red,
+            Level::Warn => ConsoleColour::Orange,
+            Level::Info => ConsoleColour::White,
+            Level::Debug => ConsoleColour::Grey,
+            Level::Trace => ConsoleColour::LightGrey
+        }
+    }
+}
+
+impl Log for GEarthLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let _ = self.handle.write_to_console_buffered(record.args().to_string(), Self::colour(record.level()));
+        }
+    }
+
+    fn flush(&self) {}
+}
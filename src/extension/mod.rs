@@ -1,2 +1,21 @@
 pub mod parsers;
+pub mod arguments;
+pub mod builder;
+pub mod capture;
 pub mod extension;
+pub mod command;
+pub mod extensionhandle;
+pub mod intercept;
+pub mod logger;
+pub mod packetlog;
+pub mod reconnect;
+pub mod scheduler;
+pub mod sendqueue;
+
+#[cfg(feature = "tokio")]
+pub mod asyncextension;
+
+#[cfg(feature = "tracing")]
+pub mod tracinglayer;
+#[cfg(feature = "persist")]
+pub mod persist;
@@ -0,0 +1,168 @@
+use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use super::extension::Extension;
+use crate::error::{Error, Result};
+use crate::protocol::hdirection::HDirection;
+use crate::protocol::hmessage::HMessage;
+use crate::protocol::hpacket::HPacket;
+use crate::services::packetinfo::packetinfo::PacketInfo;
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+
+/// Messages from a dump of G-Earth's packet logger, to replay through an extension without G-Earth
+///
+/// Every message is a line like `[Chat] <-- [2432][0][0][0][0][0][5]hello...`: the packet name (optional),
+/// `<--` for incoming or `-->` for outgoing, the header id and the body in G-Earth's notation, where bytes that aren't
+/// printable are written as `[n]`. Instead of header and body an expression like `{in:Chat}{i:0}{s:"hello"}` can be
+/// used. An expression on the line after a message is the same message shown differently and is skipped,
+/// one on its own is a message itself. Empty lines and separators like `----` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct PacketLog {
+    pub messages: Vec<HMessage>,
+    packet_infos: Vec<PacketInfo>
+}
+
+impl PacketLog {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(| e | Error::PacketLog(format!("couldn't read {}: {e}", path.display())))?
+            .parse()
+    }
+
+    /// Packet info with the names and header ids of the named messages in the log
+    pub fn packet_info_manager(&self) -> PacketInfoManager {
+        PacketInfoManager::new(self.packet_infos.clone())
+    }
+
+    /// Feeds every message through the intercepts of `ext` and returns them as the listeners left them
+    ///
+    /// Without a connection `ext` gets the [`packet_info_manager`](Self::packet_info_manager) of the log,
+    /// so intercepts by name match the named messages
+    pub fn replay<W: Debug + 'static>(&self, ext: &mut Extension<W>) -> Vec<HMessage> {
+        if ext.get_packet_info_manager().is_none() {
+            ext.set_packet_info_manager(Some(self.packet_info_manager()));
+        }
+        self.messages.iter().cloned()
+            .map(| mut message | {
+                ext.dispatch_intercept(&mut message);
+                message
+            })
+            .collect()
+    }
+
+    fn learn(&mut self, name: &str, header_id: i16, direction: &HDirection) {
+        let known = self.packet_infos.iter()
+            .any(| info | info.name == name && info.header_id == header_id as i32 && &info.destination == direction);
+        if !known {
+            self.packet_infos.push(PacketInfo {
+                header_id: header_id as i32,
+                name: name.to_string(),
+                destination: direction.clone(),
+                ..PacketInfo::default()
+            });
+        }
+    }
+
+    fn parse_message(&mut self, line: &str) -> std::result::Result<HMessage, String> {
+        let (start, end, direction) = find_arrow(line).ok_or("missing <-- or -->")?;
+        let name = match line[..start].trim() {
+            "" => None,
+            prefix => Some(prefix.strip_prefix('[')
+                .and_then(| prefix | prefix.strip_suffix(']'))
+                .ok_or_else(|| format!("expected [name] before the arrow, got {prefix}"))?)
+        };
+
+        let body = line[end..].trim_start();
+        let packet = if body.starts_with('{') {
+            self.parse_expression(body, false)?
+        } else {
+            let (header_id, body) = body.strip_prefix('[')
+                .and_then(| body | body.split_once(']'))
+                .ok_or("missing [header id]")?;
+            let header_id: i16 = header_id.parse().map_err(| _ | format!("invalid header id {header_id}"))?;
+            HPacket::from_header_id_and_bytes(header_id, parse_bytes(body))
+        };
+
+        if let Some(name) = name.filter(| name | !name.is_empty()) {
+            self.learn(name, packet.header_id(), &direction);
+        }
+        Ok(HMessage::from_packet_dir_index(packet, direction, self.messages.len() as i32))
+    }
+
+    /// Parses an expression, resolving `{in:..}` and `{out:..}` headers with the names seen so far
+    fn parse_expression(&self, expression: &str, standalone: bool) -> std::result::Result<HPacket, String> {
+        if standalone && !expression.starts_with("{in:") && !expression.starts_with("{out:") {
+            return Err(String::from("expression without <-- or --> needs an {in:..} or {out:..} header"));
+        }
+        HPacket::from_expression_with_packet_info(expression, &self.packet_info_manager()).map_err(| e | e.to_string())
+    }
+}
+
+impl FromStr for PacketLog {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut res = PacketLog::default();
+        let mut after_message = false;
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim_end_matches('\r').trim_start();
+            let message = if line.trim_end().is_empty() || line.trim_end().chars().all(| c | c == '-' || c == '=') {
+                after_message = false;
+                continue;
+            } else if line.starts_with('{') {
+                if after_message {
+                    after_message = false;
+                    continue;
+                }
+                let direction = if line.starts_with("{in:") { HDirection::ToClient } else { HDirection::ToServer };
+                res.parse_expression(line.trim_end(), true)
+                    .map(| packet | HMessage::from_packet_dir_index(packet, direction, res.messages.len() as i32))
+            } else {
+                after_message = true;
+                res.parse_message(line)
+            };
+            res.messages.push(message.map_err(| e | Error::PacketLog(format!("line {}: {e}", i + 1)))?);
+        }
+        Ok(res)
+    }
+}
+
+/// Start and end of the first `<--` or `-->` (or `<-`/`->`) in `line`, with the direction it stands for
+fn find_arrow(line: &str) -> Option<(usize, usize, HDirection)> {
+    let incoming = line.find("<-").map(| start | (start, HDirection::ToClient));
+    let outgoing = line.find("->").map(| start | (line[..start].trim_end_matches('-').len(), HDirection::ToServer));
+    let (start, direction) = match (incoming, outgoing) {
+        (Some(incoming), Some(outgoing)) => if incoming.0 <= outgoing.0 { incoming } else { outgoing },
+        (incoming, outgoing) => incoming.or(outgoing)?
+    };
+    let end = start + line[start..].find(| c | !matches!(c, '<' | '-' | '>')).unwrap_or(line.len() - start);
+    Some((start, end, direction))
+}
+
+/// Bytes written in G-Earth's notation: `[n]` for a byte `n`, other characters as their ISO-8859-1 byte
+fn parse_bytes(s: &str) -> Vec<u8> {
+    let mut res = Vec::new();
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let byte = rest.strip_prefix('[')
+            .and_then(| inner | inner.split_once(']'))
+            .filter(| (digits, _) | !digits.is_empty() && digits.chars().all(| c | c.is_ascii_digit()))
+            .and_then(| (digits, after) | Some((digits.parse::<u8>().ok()?, after)));
+        match byte {
+            Some((byte, after)) => {
+                res.push(byte);
+                rest = after;
+            },
+            None => {
+                match u8::try_from(c as u32) {
+                    Ok(byte) => res.push(byte),
+                    Err(_) => res.extend(c.to_string().as_bytes())
+                }
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    res
+}
@@ -1,11 +1,15 @@
 use std::fmt::Debug;
+use crate::error::DecodeError;
 use crate::protocol::hdirection::HDirection;
 use crate::protocol::hpacket::HPacket;
 use crate::protocol::vars::packetvariable::PacketVariable;
 
 pub trait BaseParser: PacketVariable + Clone + PartialEq + Debug {
+    /// Panics if the packet doesn't contain a valid `Self`, see [`try_parse`](Self::try_parse)
     fn parse(packet: &mut HPacket) -> Self;
+    /// Reads `Self` from the packet's read index, the error's path names the field that couldn't be read
+    fn try_parse(packet: &mut HPacket) -> Result<Self, DecodeError>;
     fn append_to_packet(&self, packet: &mut HPacket);
     fn get_direction() -> HDirection;
     fn get_packet_name() -> String;
-}
\ No newline at end of file
+}
@@ -3,7 +3,9 @@ use crate::extension::parsers::stuffdata::StuffData;
 use crate::protocol::hdirection::HDirection;
 use crate::protocol::hpacket::HPacket;
 use crate::protocol::vars::legacy::{LegacyDouble, LegacyId, LegacyLength, LegacyStringId};
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 use super::baseparser::BaseParser;
 use super::subparsers::*;
 
@@ -119,20 +121,24 @@ pub struct CraftingRecipesAvailable {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct CraftingResult {
     pub success: bool,
     pub product_data: FurnitureProductItem
 }
 
 impl PacketVariable for CraftingResult {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let success: bool = packet.read();
-        (CraftingResult {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let success: bool = reader.try_read_field("success")?;
+        Ok((CraftingResult {
             success,
-            product_data: if success { packet.read() } else { Default::default() }
-        }, packet.read_index - 6)
+            product_data: if success { reader.try_read_field("product_data")? } else { Default::default() }
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -354,22 +360,26 @@ pub struct GuideOnDutyStatus {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct GuideReportingStatus {
     pub status_code: i32,
     pub pending_ticket: Option<PendingGuideTicket>
 }
 
 impl PacketVariable for GuideReportingStatus {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let status_code = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        (Self {
+        let status_code = reader.try_read_field("status_code")?;
+
+        Ok((Self {
             status_code,
-            pending_ticket: if status_code == 1 { packet.read() } else { None }
-        }, packet.read_index - 6)
+            pending_ticket: if status_code == 1 { reader.try_read_field("pending_ticket")? } else { None }
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -909,18 +919,22 @@ pub struct RoomOccupiedTiles {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct CantConnect {
     pub reason: i32,
     pub parameter: String
 }
 
 impl PacketVariable for CantConnect {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let reason = packet.read();
-        let parameter = if reason == 3 { packet.read() } else { String::from("") };
-        (CantConnect { reason, parameter }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let reason = reader.try_read_field("reason")?;
+        let parameter = if reason == 3 { reader.try_read_field("parameter")? } else { String::from("") };
+        Ok((CantConnect { reason, parameter }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -929,7 +943,7 @@ impl PacketVariable for CantConnect {
         if self.reason == 3 {
             packet.append(self.parameter.clone());
         }
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1206,7 +1220,7 @@ pub struct NavigatorSettings {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct OfficialRooms {
     pub data: OfficialRoomsData,
     pub ad_room: Option<OfficialRoomEntryData>,
@@ -1214,14 +1228,18 @@ pub struct OfficialRooms {
 }
 
 impl PacketVariable for OfficialRooms {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let data = packet.read();
-        let ad_room = if packet.read::<i32>() > 0 { packet.read() } else { None };
-        let promoted_rooms = packet.read();
+        let data = reader.try_read_field("data")?;
+        let ad_room = if reader.try_read_field::<i32>("has_ad_room")? > 0 { reader.try_read_field("ad_room")? } else { None };
+        let promoted_rooms = reader.try_read_field("promoted_rooms")?;
 
-        (OfficialRooms { data, ad_room, promoted_rooms }, packet.read_index - 6)
+        Ok((OfficialRooms { data, ad_room, promoted_rooms }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1234,7 +1252,7 @@ impl PacketVariable for OfficialRooms {
             self.promoted_rooms.clone()
         ));
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1425,7 +1443,7 @@ pub struct YoutubeDisplayVideo {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct PollContents {
     pub id: LegacyId,
     pub start_message: String,
@@ -1435,22 +1453,28 @@ pub struct PollContents {
 }
 
 impl PacketVariable for PollContents {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (id, start_message, end_message) = packet.read();
+        let id = reader.try_read_field("id")?;
+        let start_message = reader.try_read_field("start_message")?;
+        let end_message = reader.try_read_field("end_message")?;
         let mut questions = Vec::new();
-        let question_count: LegacyLength = packet.read();
+        let question_count: LegacyLength = reader.try_read_field("question_count")?;
         for _ in 0..*question_count {
-            let mut question: PollQuestion = packet.read();
-            question.children = packet.read();
+            let mut question: PollQuestion = reader.try_read_field(format!("questions[{}]", questions.len()))?;
+            question.children = reader.try_read_field(format!("questions[{}].children", questions.len()))?;
             questions.push(question);
         }
-        let nps_poll = packet.read();
+        let nps_poll = reader.try_read_field("nps_poll")?;
 
-        (PollContents {
+        Ok((PollContents {
             id, start_message, end_message, questions, nps_poll
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1464,7 +1488,7 @@ impl PacketVariable for PollContents {
         }
         packet.append(self.nps_poll);
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1754,7 +1778,7 @@ pub struct FurnitureAliases {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct HeightMap {
     pub width: i32,
     pub height: i32,
@@ -1778,19 +1802,24 @@ impl HeightMap {
 }
 
 impl PacketVariable for HeightMap {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (width, size): (i32, LegacyLength) = packet.read();
+        let width: i32 = reader.try_read_field("width")?;
+        let size: LegacyLength = reader.try_read_field("size")?;
         let height = *size as i32/width;
         let mut tiles = Vec::new();
         for _ in 0..*size {
-            tiles.push(packet.read());
+            tiles.push(reader.try_read_field(format!("tiles[{}]", tiles.len()))?);
         }
 
-        (Self {
+        Ok((Self {
             width, height, tiles
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1806,29 +1835,33 @@ impl PacketVariable for HeightMap {
             packet.append(tile.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct HeightMapUpdate {
     pub tile_updates: Vec<HeightMapTileUpdate>
 }
 
 impl PacketVariable for HeightMapUpdate {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
         let mut tile_updates = Vec::new();
-        let count: i8 = packet.read();
+        let count: i8 = reader.try_read_field("count")?;
         for _ in 0..count {
-            tile_updates.push(packet.read());
+            tile_updates.push(reader.try_read_field(format!("tile_updates[{}]", tile_updates.len()))?);
         }
 
-        (Self {
+        Ok((Self {
             tile_updates
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1839,26 +1872,30 @@ impl PacketVariable for HeightMapUpdate {
             packet.append(update.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct ItemAdd {
     pub item: WallItem
 }
 
 impl PacketVariable for ItemAdd {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let mut item: WallItem = packet.read();
-        item.owner_name = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        (Self {
+        let mut item: WallItem = reader.try_read_field("item")?;
+        item.owner_name = reader.try_read_field("owner_name")?;
+
+        Ok((Self {
             item
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1894,21 +1931,25 @@ pub struct ItemUpdate {
 }
 
 #[derive(BaseParser, Clone, Debug, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct ObjectAdd {
     pub object: FloorItem
 }
 
 impl PacketVariable for ObjectAdd {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let mut object: FloorItem = packet.read();
-        object.owner_name = packet.read();
+        let mut object: FloorItem = reader.try_read_field("object")?;
+        object.owner_name = reader.try_read_field("owner_name")?;
 
-        (Self {
+        Ok((Self {
             object
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1974,7 +2015,7 @@ pub struct RoomVisualizationSettings {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct SlideObjectBundle {
     pub old_x: i32,
     pub old_y: i32,
@@ -1989,18 +2030,27 @@ pub struct SlideObjectBundle {
 }
 
 impl PacketVariable for SlideObjectBundle {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (old_x, old_y, new_x, new_y, objects_z, id) = packet.read();
-        let avatar_movement_type  = packet.read::<Option<i32>>().unwrap_or(-1);
+        let old_x = reader.try_read_field("old_x")?;
+        let old_y = reader.try_read_field("old_y")?;
+        let new_x = reader.try_read_field("new_x")?;
+        let new_y = reader.try_read_field("new_y")?;
+        let objects_z = reader.try_read_field("objects_z")?;
+        let id = reader.try_read_field("id")?;
+        let avatar_movement_type  = reader.try_read_field::<Option<i32>>("avatar_movement_type")?.unwrap_or(-1);
 
-        (Self {
+        Ok((Self {
             old_x, old_y, new_x, new_y, objects_z, id, avatar_movement_type,
-            avatar_id: if vec![1, 2].contains(&avatar_movement_type) { packet.read() } else { LegacyId::default() },
-            avatar_old_z: if vec![1, 2].contains(&avatar_movement_type) { packet.read() } else { LegacyDouble::default() },
-            avatar_new_z: if vec![1, 2].contains(&avatar_movement_type) { packet.read() } else { LegacyDouble::default() }
-        }, packet.read_index - 6)
+            avatar_id: if vec![1, 2].contains(&avatar_movement_type) { reader.try_read_field("avatar_id")? } else { LegacyId::default() },
+            avatar_old_z: if vec![1, 2].contains(&avatar_movement_type) { reader.try_read_field("avatar_old_z")? } else { LegacyDouble::default() },
+            avatar_new_z: if vec![1, 2].contains(&avatar_movement_type) { reader.try_read_field("avatar_new_z")? } else { LegacyDouble::default() }
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2443,7 +2493,7 @@ pub struct FriendListFragment {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct FriendListUpdate {
     pub cats: Vec<FriendCategoryData>,
     pub removed_friend_ids: Vec<LegacyId>,
@@ -2452,26 +2502,30 @@ pub struct FriendListUpdate {
 }
 
 impl PacketVariable for FriendListUpdate {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let cats = packet.read();
-        let count: LegacyLength = packet.read();
+        let cats = reader.try_read_field("cats")?;
+        let count: LegacyLength = reader.try_read_field("count")?;
         let mut removed_friend_ids = Vec::new();
         let mut updated_friends = Vec::new();
         let mut added_friends = Vec::new();
-        for _ in 0..*count {
-            match packet.read::<i32>() {
-                -1 => removed_friend_ids.push(packet.read()),
-                0 => updated_friends.push(packet.read()),
-                1 => added_friends.push(packet.read()),
+        for i in 0..*count {
+            match reader.try_read_field::<i32>(format!("updates[{i}].type"))? {
+                -1 => removed_friend_ids.push(reader.try_read_field(format!("removed_friend_ids[{}]", removed_friend_ids.len()))?),
+                0 => updated_friends.push(reader.try_read_field(format!("updated_friends[{}]", updated_friends.len()))?),
+                1 => added_friends.push(reader.try_read_field(format!("added_friends[{}]", added_friends.len()))?),
                 _ => {}
             }
         }
 
-        (Self {
+        Ok((Self {
             cats, removed_friend_ids, updated_friends, added_friends
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2490,7 +2544,7 @@ impl PacketVariable for FriendListUpdate {
             packet.append((0i32, friend.clone()));
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -2566,22 +2620,26 @@ pub struct NewFriendRequest {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct RoomInviteError {
     pub error_code: i32,
     pub failed_recipients: Vec<LegacyId>
 }
 
 impl PacketVariable for RoomInviteError {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let error_code = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        (Self {
+        let error_code = reader.try_read_field("error_code")?;
+
+        Ok((Self {
             error_code,
-            failed_recipients: if error_code == 1 { packet.read() } else { Vec::new() }
-        }, packet.read_index - 6)
+            failed_recipients: if error_code == 1 { reader.try_read_field("failed_recipients")? } else { Vec::new() }
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -3633,27 +3691,31 @@ pub struct MarketplaceMakeOfferResult {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 0)]
+#[to(direction = 0, manual)]
 pub struct MarketPlaceOffers {
     pub offers: Vec<MarketPlaceOffer>,
     pub total_items_found: i32
 }
 
 impl PacketVariable for MarketPlaceOffers {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
         let mut offers: Vec<MarketPlaceOffer> = Vec::new();
-        for _ in 0..*packet.read::<LegacyLength>() {
-            let mut offer: MarketPlaceOffer = packet.read();
-            offer.offer_count = packet.read();
+        for _ in 0..*reader.try_read_field::<LegacyLength>("offers")? {
+            let mut offer: MarketPlaceOffer = reader.try_read_field(format!("offers[{}]", offers.len()))?;
+            offer.offer_count = reader.try_read_field(format!("offers[{}].offer_count", offers.len()))?;
             offers.push(offer);
         }
-        let total_items_found = packet.read();
+        let total_items_found = reader.try_read_field("total_items_found")?;
 
-        (Self {
+        Ok((Self {
             offers, total_items_found
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -3664,7 +3726,7 @@ impl PacketVariable for MarketPlaceOffers {
         }
         packet.append(self.total_items_found);
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -4,7 +4,9 @@ use miniz_oxide::inflate::decompress_to_vec_zlib;
 use crate::protocol::hdirection::HDirection;
 use crate::protocol::hpacket::HPacket;
 use crate::protocol::vars::legacy::{LegacyId, LegacyLength};
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 use super::baseparser::BaseParser;
 use super::subparsers::*;
 
@@ -103,7 +105,7 @@ pub struct GetOccupiedTiles {}
 pub struct GetRoomEntryTile {}
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct UpdateFloorProperties {
     pub data: String,
     pub entry_point_x: i32,
@@ -115,18 +117,22 @@ pub struct UpdateFloorProperties {
 }
 
 impl PacketVariable for UpdateFloorProperties {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            data: packet.read(),
-            entry_point_x: packet.read::<Option<i32>>().unwrap_or(-1),
-            entry_point_y: packet.read::<Option<i32>>().unwrap_or(-1),
-            entry_point_dir: packet.read::<Option<i32>>().unwrap_or(-1),
-            wall_thickness: packet.read::<Option<i32>>().unwrap_or(-1),
-            floor_thickness: packet.read::<Option<i32>>().unwrap_or(-1),
-            fixed_walls_height: packet.read::<Option<i32>>().unwrap_or(-1)
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            data: reader.try_read_field("data")?,
+            entry_point_x: reader.try_read_field::<Option<i32>>("entry_point_x")?.unwrap_or(-1),
+            entry_point_y: reader.try_read_field::<Option<i32>>("entry_point_y")?.unwrap_or(-1),
+            entry_point_dir: reader.try_read_field::<Option<i32>>("entry_point_dir")?.unwrap_or(-1),
+            wall_thickness: reader.try_read_field::<Option<i32>>("wall_thickness")?.unwrap_or(-1),
+            floor_thickness: reader.try_read_field::<Option<i32>>("floor_thickness")?.unwrap_or(-1),
+            fixed_walls_height: reader.try_read_field::<Option<i32>>("fixed_walls_height")?.unwrap_or(-1)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -181,7 +187,7 @@ pub struct ChangeEmail {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct CreateGuild {
     pub name: String,
     pub description: String,
@@ -192,19 +198,27 @@ pub struct CreateGuild {
 }
 
 impl PacketVariable for CreateGuild {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (name, description, room_id, primary_color_id, secondary_color_id) = packet.read();
-        let size: LegacyLength = packet.read();
+        let name = reader.try_read_field("name")?;
+        let description = reader.try_read_field("description")?;
+        let room_id = reader.try_read_field("room_id")?;
+        let primary_color_id = reader.try_read_field("primary_color_id")?;
+        let secondary_color_id = reader.try_read_field("secondary_color_id")?;
+        let size: LegacyLength = reader.try_read_field("size")?;
         let mut badge_settings: Vec<GuildBadgeSettings> = Vec::new();
         for _ in 0..*size/3 {
-            badge_settings.push(packet.read());
+            badge_settings.push(reader.try_read_field(format!("badge_settings[{}]", badge_settings.len()))?);
         }
 
-        (Self {
+        Ok((Self {
             name, description, room_id, primary_color_id, secondary_color_id, badge_settings
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -219,7 +233,7 @@ impl PacketVariable for CreateGuild {
             packet.append(setting.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -396,26 +410,30 @@ pub struct UnignoreUser {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct UpdateGuildBadge {
     pub group_id: LegacyId,
     pub badge_settings: Vec<GuildBadgeSettings>
 }
 
 impl PacketVariable for UpdateGuildBadge {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let group_id = packet.read();
-        let size: LegacyLength = packet.read();
+        let group_id = reader.try_read_field("group_id")?;
+        let size: LegacyLength = reader.try_read_field("size")?;
         let mut badge_settings: Vec<GuildBadgeSettings> = Vec::new();
         for _ in 0..*size/3 {
-            badge_settings.push(packet.read());
+            badge_settings.push(reader.try_read_field(format!("badge_settings[{}]", badge_settings.len()))?);
         }
 
-        (Self {
+        Ok((Self {
             group_id, badge_settings
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -426,7 +444,7 @@ impl PacketVariable for UpdateGuildBadge {
             packet.append(setting.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1299,7 +1317,7 @@ pub struct CloseIssues {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct DefaultSanction {
     pub user_id: LegacyId,
     pub topic_id: i32,
@@ -1308,15 +1326,19 @@ pub struct DefaultSanction {
 }
 
 impl PacketVariable for DefaultSanction {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            topic_id: packet.read(),
-            message: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            message: reader.try_read_field("message")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1370,7 +1392,7 @@ pub struct GetUserChatlog {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct ModAlert {
     pub user_id: LegacyId,
     pub message: String,
@@ -1379,15 +1401,19 @@ pub struct ModAlert {
 }
 
 impl PacketVariable for ModAlert {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            message: packet.read(),
-            topic_id: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            message: reader.try_read_field("message")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1404,7 +1430,7 @@ impl PacketVariable for ModAlert {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct ModBan {
     pub user_id: LegacyId,
     pub message: String,
@@ -1415,17 +1441,21 @@ pub struct ModBan {
 }
 
 impl PacketVariable for ModBan {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            message: packet.read(),
-            topic_id: packet.read(),
-            sanction_type_id: packet.read(),
-            is_perm_ban: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            message: reader.try_read_field("message")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            sanction_type_id: reader.try_read_field("sanction_type_id")?,
+            is_perm_ban: reader.try_read_field("is_perm_ban")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1461,7 +1491,7 @@ pub struct ModeratorAction {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct ModKick {
     pub user_id: LegacyId,
     pub message: String,
@@ -1470,15 +1500,19 @@ pub struct ModKick {
 }
 
 impl PacketVariable for ModKick {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            message: packet.read(),
-            topic_id: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            message: reader.try_read_field("message")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1495,7 +1529,7 @@ impl PacketVariable for ModKick {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct ModMessage {
     pub user_id: LegacyId,
     pub message: String,
@@ -1506,17 +1540,21 @@ pub struct ModMessage {
 }
 
 impl PacketVariable for ModMessage {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            message: packet.read(),
-            _unknown1: packet.read(),
-            _unknown2: packet.read(),
-            topic_id: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            message: reader.try_read_field("message")?,
+            _unknown1: reader.try_read_field("_unknown1")?,
+            _unknown2: reader.try_read_field("_unknown2")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1535,7 +1573,7 @@ impl PacketVariable for ModMessage {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct ModMute {
     pub user_id: LegacyId,
     pub message: String,
@@ -1544,15 +1582,19 @@ pub struct ModMute {
 }
 
 impl PacketVariable for ModMute {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            message: packet.read(),
-            topic_id: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            message: reader.try_read_field("message")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1586,7 +1628,7 @@ pub struct ModToolSanction {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct ModTradingLock {
     pub user_id: LegacyId,
     pub message: String,
@@ -1596,16 +1638,20 @@ pub struct ModTradingLock {
 }
 
 impl PacketVariable for ModTradingLock {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        (Self {
-            user_id: packet.read(),
-            message: packet.read(),
-            length_min: packet.read(),
-            topic_id: packet.read(),
-            issue_id: packet.read::<Option<LegacyId>>().unwrap_or(LegacyId(-1))
-        }, packet.read_index - 6)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        Ok((Self {
+            user_id: reader.try_read_field("user_id")?,
+            message: reader.try_read_field("message")?,
+            length_min: reader.try_read_field("length_min")?,
+            topic_id: reader.try_read_field("topic_id")?,
+            issue_id: reader.try_read_field::<Option<LegacyId>>("issue_id")?.unwrap_or(LegacyId(-1))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1855,23 +1901,27 @@ pub struct RequestABadge {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct SetActivatedBadges {
     pub badges: [String; 5]
 }
 
 impl PacketVariable for SetActivatedBadges {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let indexed_badges = packet.read::<[(i32, String); 5]>();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let indexed_badges: [(i32, String); 5] = reader.try_read_field("badges")?;
 
-        (Self {
+        Ok((Self {
             badges: [
                 indexed_badges[0].1.clone(), indexed_badges[1].1.clone(),
                 indexed_badges[2].1.clone(), indexed_badges[3].1.clone(),
                 indexed_badges[4].1.clone()
             ]
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1881,7 +1931,7 @@ impl PacketVariable for SetActivatedBadges {
             packet.append((i as i32, self.badges[i].clone()));
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1971,24 +2021,28 @@ pub struct SaveUserNftWardrobe {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct NewUserExperienceGetGifts {
     pub selections: Vec<NewUserExperienceGetGiftsSelection> // (day_index, step_index, gift_index)
 }
 
 impl PacketVariable for NewUserExperienceGetGifts {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
         let mut selections: Vec<NewUserExperienceGetGiftsSelection> = Vec::new();
-        let size: LegacyLength = packet.read();
+        let size: LegacyLength = reader.try_read_field("size")?;
         for _ in 0..*size/3 {
-            selections.push(packet.read());
+            selections.push(reader.try_read_field(format!("selections[{}]", selections.len()))?);
         }
 
-        (Self {
+        Ok((Self {
             selections
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1999,7 +2053,7 @@ impl PacketVariable for NewUserExperienceGetGifts {
             packet.append(selection);
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -2162,26 +2216,32 @@ pub struct SetItemData {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct SetObjectData {
     pub item_id: LegacyId,
     pub data: HashMap<String, String>
 }
 
 impl PacketVariable for SetObjectData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let item_id = packet.read();
-        let size: LegacyLength = packet.read();
+        let item_id = reader.try_read_field("item_id")?;
+        let size: LegacyLength = reader.try_read_field("size")?;
         let mut data: HashMap<String, String> = HashMap::new();
         for _ in 0..*size/2 {
-            data.insert(packet.read(), packet.read());
+            let key: String = reader.try_read_field(format!("data[{}].key", data.len()))?;
+            let value = reader.try_read_field(format!("data[{}].value", data.len()))?;
+            data.insert(key, value);
         }
 
-        (Self {
+        Ok((Self {
             item_id, data
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2192,7 +2252,7 @@ impl PacketVariable for SetObjectData {
             packet.append((key, val));
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -3248,19 +3308,23 @@ pub struct PublishPhoto {}
 pub struct PurchasePhoto {}
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct RenderRoom {
     pub json_string: String
 }
 
 impl PacketVariable for RenderRoom {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let compressed: Vec<u8> = packet.read();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let compressed: Vec<u8> = reader.try_read_field("compressed")?;
         let decompressed = decompress_to_vec_zlib(compressed.as_slice()).expect("Couldn't decompress RenderRoom packet");
-        (Self {
+        Ok((Self {
             json_string: String::from_utf8(decompressed).expect("Couldn't form string from decompressed RenderRoom bytes")
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -3270,19 +3334,23 @@ impl PacketVariable for RenderRoom {
 }
 
 #[derive(BaseParser, Clone, Debug, Default, PartialEq)]
-#[to(direction = 1)]
+#[to(direction = 1, manual)]
 pub struct RenderRoomThumbnail {
     pub json_string: String
 }
 
 impl PacketVariable for RenderRoomThumbnail {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let compressed: Vec<u8> = packet.read();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let compressed: Vec<u8> = reader.try_read_field("compressed")?;
         let decompressed = decompress_to_vec_zlib(compressed.as_slice()).expect("Couldn't decompress RenderRoom packet");
-        (Self {
+        Ok((Self {
             json_string: String::from_utf8(decompressed).expect("Couldn't form string from decompressed RenderRoom bytes")
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use crate::extension::parsers::stuffdata::StuffData::{CrackableStuffData, EmptyStuffData, HighScoreStuffData, IntArrayStuffData, LegacyStuffData, MapStuffData, StringArrayStuffData, VoteResultStuffData};
-use crate::protocol::hpacket::HPacket;
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum StuffData {
@@ -67,50 +68,54 @@ impl Default for StuffData {
 }
 
 impl PacketVariable for StuffData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let cat: i32 = packet.read();
+        let cat: i32 = reader.try_read_field("cat")?;
 
-        (match cat & 255 {
+        Ok((match cat & 255 {
             0 => LegacyStuffData {
-                legacy_string: packet.read(),
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                legacy_string: reader.try_read_field("legacy_string")?,
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
             1 => MapStuffData {
-                map: packet.read(),
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                map: reader.try_read_field("map")?,
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
             2 => StringArrayStuffData {
-                values: packet.read(),
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                values: reader.try_read_field("values")?,
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
             3 => VoteResultStuffData {
-                legacy_string: packet.read(),
-                result: packet.read(),
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                legacy_string: reader.try_read_field("legacy_string")?,
+                result: reader.try_read_field("result")?,
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
             4 => EmptyStuffData {
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
             5 => IntArrayStuffData {
-                values: packet.read(),
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                values: reader.try_read_field("values")?,
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
             6 => HighScoreStuffData {
-                legacy_string: packet.read(),
-                score_type: packet.read(),
-                clear_type: packet.read(),
-                entries: packet.read()
+                legacy_string: reader.try_read_field("legacy_string")?,
+                score_type: reader.try_read_field("score_type")?,
+                clear_type: reader.try_read_field("clear_type")?,
+                entries: reader.try_read_field("entries")?
             },
             7 => CrackableStuffData {
-                legacy_string: packet.read(),
-                hits: packet.read(),
-                target: packet.read(),
-                unique_serial_data: if cat & 256 > 0 { packet.read() } else { None }
+                legacy_string: reader.try_read_field("legacy_string")?,
+                hits: reader.try_read_field("hits")?,
+                target: reader.try_read_field("target")?,
+                unique_serial_data: if cat & 256 > 0 { reader.try_read_field("unique_serial_data")? } else { None }
             },
-            _ => panic!("Unknown stuff data type")
-        }, packet.read_index - 6)
+            _ => return Err(DecodeError::new(reader.position(), "Unknown stuff data type"))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
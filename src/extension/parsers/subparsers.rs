@@ -3,7 +3,9 @@ use std::collections::HashMap;
 use crate::extension::parsers::stuffdata::StuffData;
 use crate::protocol::hpacket::HPacket;
 use crate::protocol::vars::legacy::{LegacyId, LegacyLength, LegacyDouble, LegacyStringId};
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 
 // WIN63-202307010021-160474147
 
@@ -317,35 +319,56 @@ pub struct GuestRoomData {
 }
 
 impl PacketVariable for GuestRoomData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let (flat_id, room_name, owner_id, owner_name, door_mode, user_count, max_user_count,
-            description, trade_mode, score, ranking, category_id, tags) = packet.read();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let multi_use: i32 = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let flat_id = reader.try_read_field("flat_id")?;
+        let room_name = reader.try_read_field("room_name")?;
+        let owner_id = reader.try_read_field("owner_id")?;
+        let owner_name = reader.try_read_field("owner_name")?;
+        let door_mode = reader.try_read_field("door_mode")?;
+        let user_count = reader.try_read_field("user_count")?;
+        let max_user_count = reader.try_read_field("max_user_count")?;
+        let description = reader.try_read_field("description")?;
+        let trade_mode = reader.try_read_field("trade_mode")?;
+        let score = reader.try_read_field("score")?;
+        let ranking = reader.try_read_field("ranking")?;
+        let category_id = reader.try_read_field("category_id")?;
+        let tags = reader.try_read_field("tags")?;
+
+        let multi_use: i32 = reader.try_read_field("multi_use")?;
 
         let official_room_pic_ref =
-            if (multi_use & 1) > 0 { Some(packet.read()) }
+            if (multi_use & 1) > 0 { Some(reader.try_read_field("official_room_pic_ref")?) }
             else { None };
 
-        let (habbo_group_id, group_name, group_badge_code) =
-            if (multi_use & 2) > 0 { packet.read() }
-            else { (None, None, None) };
+        let (mut habbo_group_id, mut group_name, mut group_badge_code) = (None, None, None);
+        if (multi_use & 2) > 0 {
+            habbo_group_id = reader.try_read_field("habbo_group_id")?;
+            group_name = reader.try_read_field("group_name")?;
+            group_badge_code = reader.try_read_field("group_badge_code")?;
+        }
 
-        let (room_ad_name, room_ad_description, room_ad_expires_in_min) =
-            if (multi_use & 4) > 0 { packet.read() }
-            else { (None, None, None) };
+        let (mut room_ad_name, mut room_ad_description, mut room_ad_expires_in_min) = (None, None, None);
+        if (multi_use & 4) > 0 {
+            room_ad_name = reader.try_read_field("room_ad_name")?;
+            room_ad_description = reader.try_read_field("room_ad_description")?;
+            room_ad_expires_in_min = reader.try_read_field("room_ad_expires_in_min")?;
+        }
 
         let show_owner = (multi_use & 8) > 0;
         let allow_pets = (multi_use & 16) > 0;
         let display_room_entry_ad = (multi_use & 32) > 0;
 
-        ( Self { flat_id, room_name, owner_id, owner_name, door_mode, user_count, max_user_count,
+        Ok(( Self { flat_id, room_name, owner_id, owner_name, door_mode, user_count, max_user_count,
             description, trade_mode, score, ranking, category_id, tags, official_room_pic_ref,
             habbo_group_id, group_name, group_badge_code, room_ad_name, room_ad_description,
             room_ad_expires_in_min, show_owner, allow_pets, display_room_entry_ad },
-          packet.read_index - 6
-        )
+          reader.position()
+        ))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -386,7 +409,7 @@ impl PacketVariable for GuestRoomData {
             packet.append((self.room_ad_name.clone(), self.room_ad_description.clone(), self.room_ad_expires_in_min));
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -406,13 +429,20 @@ pub struct GuestRoomSearchResultData {
 }
 
 impl PacketVariable for GuestRoomSearchResultData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (search_type, search_param, rooms, has_ad) = packet.read();
-        let ad = if has_ad { packet.read() } else { None };
+        let search_type = reader.try_read_field("search_type")?;
+        let search_param = reader.try_read_field("search_param")?;
+        let rooms = reader.try_read_field("rooms")?;
+        let has_ad = reader.try_read_field("has_ad")?;
+        let ad = if has_ad { reader.try_read_field("ad")? } else { None };
 
-        (GuestRoomSearchResultData { search_type, search_param, rooms, ad }, packet.read_index - 6)
+        Ok((GuestRoomSearchResultData { search_type, search_param, rooms, ad }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -421,7 +451,7 @@ impl PacketVariable for GuestRoomSearchResultData {
         packet.append((self.search_type, self.search_param.clone(), self.rooms.clone(),
                        self.ad.is_some(), self.ad.clone()));
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -442,26 +472,37 @@ pub struct OfficialRoomEntryData {
 }
 
 impl PacketVariable for OfficialRoomEntryData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let (index, popup_caption, popup_desc, show_details, pic_text,
-            pic_ref, folder_id, user_count, entry_type) = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        let index = reader.try_read_field("index")?;
+        let popup_caption = reader.try_read_field("popup_caption")?;
+        let popup_desc = reader.try_read_field("popup_desc")?;
+        let show_details = reader.try_read_field("show_details")?;
+        let pic_text = reader.try_read_field("pic_text")?;
+        let pic_ref = reader.try_read_field("pic_ref")?;
+        let folder_id = reader.try_read_field("folder_id")?;
+        let user_count = reader.try_read_field("user_count")?;
+        let entry_type = reader.try_read_field("entry_type")?;
 
         let (mut tag, mut guest_room_data, mut open) = (None, None, None);
         if entry_type == 1 {
-            tag = packet.read();
+            tag = reader.try_read_field("tag")?;
         } else if entry_type == 2 {
-            guest_room_data = packet.read();
+            guest_room_data = reader.try_read_field("guest_room_data")?;
         } else {
-            open = packet.read();
+            open = reader.try_read_field("open")?;
         }
 
-        (
+        Ok((
             OfficialRoomEntryData { index, popup_caption, popup_desc, show_details, pic_text,
                 pic_ref, folder_id, user_count, entry_type, tag, guest_room_data, open },
-            packet.read_index - 6
-        )
+            reader.position()
+        ))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -478,7 +519,7 @@ impl PacketVariable for OfficialRoomEntryData {
             packet.append(self.open);
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -581,25 +622,30 @@ pub enum ChatRecordDataValue {
 }
 
 impl PacketVariable for ChatRecordData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let record_type = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        let record_type = reader.try_read_field("record_type")?;
         let mut context = HashMap::new();
-        let length: i16 = packet.read();
+        let length: i16 = reader.try_read_field("length")?;
         for _ in 0..length {
-            let key = packet.read();
-            let val = match packet.read::<i8>() {
-                0 => ChatRecordDataValue::Bool(packet.read()),
-                1 => ChatRecordDataValue::Int(packet.read()),
-                2 => ChatRecordDataValue::String(packet.read()),
-                _ => panic!("Unknown data type")
+            let field = format!("context[{}]", context.len());
+            let key: String = reader.try_read_field(format!("{field}.key"))?;
+            let val = match reader.try_read_field::<i8>(format!("{field}.type"))? {
+                0 => ChatRecordDataValue::Bool(reader.try_read_field(format!("{field}.value"))?),
+                1 => ChatRecordDataValue::Int(reader.try_read_field(format!("{field}.value"))?),
+                2 => ChatRecordDataValue::String(reader.try_read_field(format!("{field}.value"))?),
+                _ => return Err(DecodeError::new(reader.position(), "Unknown data type"))
             };
             context.insert(key, val);
         }
-        let chat_log = packet.read();
+        let chat_log = reader.try_read_field("chat_log")?;
 
-        (ChatRecordData { record_type, context, chat_log }, packet.read_index - 6)
+        Ok((ChatRecordData { record_type, context, chat_log }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -617,7 +663,7 @@ impl PacketVariable for ChatRecordData {
         }
         packet.append(self.chat_log.clone());
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -691,16 +737,20 @@ pub struct RoomData {
 }
 
 impl PacketVariable for RoomData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let exists = packet.read();
-        (RoomData {
+        let exists = reader.try_read_field("exists")?;
+        Ok((RoomData {
             exists,
-            name: if exists { packet.read() } else { None },
-            desc: if exists { packet.read() } else { None },
-            tags: if exists { packet.read() } else { None },
-        }, packet.read_index - 6)
+            name: if exists { reader.try_read_field("name")? } else { None },
+            desc: if exists { reader.try_read_field("desc")? } else { None },
+            tags: if exists { reader.try_read_field("tags")? } else { None },
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -711,7 +761,7 @@ impl PacketVariable for RoomData {
             packet.append((self.name.clone(), self.desc.clone(), self.tags.clone()));
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -774,24 +824,32 @@ pub struct PollQuestion {
 }
 
 impl PacketVariable for PollQuestion {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (question_id, sort_order, question_type, question_text, question_category,
-            question_answer_type) = packet.read();
-        let question_answer_count: LegacyLength = packet.read();
+        let question_id = reader.try_read_field("question_id")?;
+        let sort_order = reader.try_read_field("sort_order")?;
+        let question_type = reader.try_read_field("question_type")?;
+        let question_text = reader.try_read_field("question_text")?;
+        let question_category = reader.try_read_field("question_category")?;
+        let question_answer_type = reader.try_read_field("question_answer_type")?;
+        let question_answer_count: LegacyLength = reader.try_read_field("question_answer_count")?;
         let mut question_choices = Vec::new();
         let children = Vec::new();
         if question_type == 1 || question_type == 2 {
             for _ in 0..*question_answer_count {
-                question_choices.push(packet.read())
+                question_choices.push(reader.try_read_field(format!("question_choices[{}]", question_choices.len()))?)
             }
         }
 
-        (PollQuestion {
+        Ok((PollQuestion {
             question_id, sort_order, question_type, question_text, question_category,
             question_answer_type, question_answer_count, question_choices, children
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -807,7 +865,7 @@ impl PacketVariable for PollQuestion {
             }
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -830,28 +888,35 @@ pub struct QuestionData {
 }
 
 impl PacketVariable for QuestionData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let (id, number, question_type, content) = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        let id = reader.try_read_field("id")?;
+        let number = reader.try_read_field("number")?;
+        let question_type = reader.try_read_field("question_type")?;
+        let content = reader.try_read_field("content")?;
 
         let mut selection_values = Vec::new();
         let mut selections = Vec::new();
         let mut selection_min = -1;
 
         if question_type == 1 || question_type == 2 {
-            selection_min = packet.read();
-            let count: i32 = packet.read();
+            selection_min = reader.try_read_field("selection_min")?;
+            let count: i32 = reader.try_read_field("count")?;
             for _ in 0..count {
-                selection_values.push(packet.read());
-                selections.push(packet.read());
+                selection_values.push(reader.try_read_field(format!("selection_values[{}]", selection_values.len()))?);
+                selections.push(reader.try_read_field(format!("selections[{}]", selections.len()))?);
             }
         }
 
-        (Self {
+        Ok((Self {
             id, number, question_type, content,
             selection_min, selection_values, selections
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -872,7 +937,7 @@ impl PacketVariable for QuestionData {
             }
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -921,10 +986,14 @@ pub struct CatalogPageMessageProductData {
 }
 
 impl PacketVariable for CatalogPageMessageProductData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let product_type: String = packet.read();
+        let product_type: String = reader.try_read_field("product_type")?;
         let mut furni_class_id = LegacyId(-1);
         let extra_param: String;
         let mut product_count = 1;
@@ -932,15 +1001,19 @@ impl PacketVariable for CatalogPageMessageProductData {
         let mut unique_limited_item_series_size = -1;
         let mut unique_limited_items_left = -1;
         if product_type.ne(&String::from("b")) {
-            (furni_class_id, extra_param, product_count, unique_limited_item) = packet.read();
+            furni_class_id = reader.try_read_field("furni_class_id")?;
+            extra_param = reader.try_read_field("extra_param")?;
+            product_count = reader.try_read_field("product_count")?;
+            unique_limited_item = reader.try_read_field("unique_limited_item")?;
             if unique_limited_item {
-                (unique_limited_item_series_size, unique_limited_items_left) = packet.read();
+                unique_limited_item_series_size = reader.try_read_field("unique_limited_item_series_size")?;
+                unique_limited_items_left = reader.try_read_field("unique_limited_items_left")?;
             }
         } else {
-            extra_param = packet.read();
+            extra_param = reader.try_read_field("extra_param")?;
         }
 
-        (Self {
+        Ok((Self {
             product_type,
             furni_class_id,
             extra_param: extra_param.clone(),
@@ -948,7 +1021,7 @@ impl PacketVariable for CatalogPageMessageProductData {
             unique_limited_item,
             unique_limited_item_series_size,
             unique_limited_items_left
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -971,7 +1044,7 @@ impl PacketVariable for CatalogPageMessageProductData {
             packet.append(self.extra_param.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -988,20 +1061,29 @@ pub struct FrontPageItem {
 }
 
 impl PacketVariable for FrontPageItem {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let mut res = Self::default();
-        (res.position, res.item_name, res.item_promo_image, res.item_type) = packet.read();
+        let mut res = Self {
+            position: reader.try_read_field("position")?,
+            item_name: reader.try_read_field("item_name")?,
+            item_promo_image: reader.try_read_field("item_promo_image")?,
+            item_type: reader.try_read_field("item_type")?,
+            ..Default::default()
+        };
         match res.item_type {
-            0 => res.catalogue_page_location = packet.read(),
-            1 => res.product_offer_id = packet.read(),
-            2 => res.product_code = packet.read(),
-            _ => panic!("Unknown item type")
+            0 => res.catalogue_page_location = reader.try_read_field("catalogue_page_location")?,
+            1 => res.product_offer_id = reader.try_read_field("product_offer_id")?,
+            2 => res.product_code = reader.try_read_field("product_code")?,
+            _ => return Err(DecodeError::new(reader.position(), "Unknown item type"))
         }
-        res.expiration_time = packet.read();
+        res.expiration_time = reader.try_read_field("expiration_time")?;
 
-        (res.clone(), packet.read_index - 6)
+        Ok((res.clone(), reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1019,7 +1101,7 @@ impl PacketVariable for FrontPageItem {
         }
         packet.append(self.expiration_time);
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1079,16 +1161,20 @@ pub struct HeightMapTile {
 }
 
 impl PacketVariable for HeightMapTile {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let val: i16 = packet.read();
+        let val: i16 = reader.try_read_field("val")?;
 
-        (Self {
+        Ok((Self {
             height: if val < 0 { -1.0 } else { (val & 16383) as f32 / 256.0 },
             is_stacking_blocked: (val & 16384) > 0,
             is_room_tile: val >= 0
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1127,15 +1213,25 @@ pub struct WallItem {
 }
 
 impl PacketVariable for WallItem {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (id, type_id, location, data_str, seconds_to_expiration, usage_policy, owner_id) = packet.read();
+        let id = reader.try_read_field("id")?;
+        let type_id = reader.try_read_field("type_id")?;
+        let location = reader.try_read_field("location")?;
+        let data_str = reader.try_read_field("data_str")?;
+        let seconds_to_expiration = reader.try_read_field("seconds_to_expiration")?;
+        let usage_policy = reader.try_read_field("usage_policy")?;
+        let owner_id = reader.try_read_field("owner_id")?;
 
-        (Self {
+        Ok((Self {
             id, type_id, location, data_str, seconds_to_expiration, usage_policy, owner_id,
             owner_name: None
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1165,16 +1261,31 @@ pub struct FloorItem {
 }
 
 impl PacketVariable for FloorItem {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        let (id, type_id, x, y, dir, z, size_z, extra, data, expiry_time, usage_policy, owner_id) = packet.read();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        (Self {
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        let id = reader.try_read_field("id")?;
+        let type_id = reader.try_read_field("type_id")?;
+        let x = reader.try_read_field("x")?;
+        let y = reader.try_read_field("y")?;
+        let dir = reader.try_read_field("dir")?;
+        let z = reader.try_read_field("z")?;
+        let size_z = reader.try_read_field("size_z")?;
+        let extra = reader.try_read_field("extra")?;
+        let data = reader.try_read_field("data")?;
+        let expiry_time = reader.try_read_field("expiry_time")?;
+        let usage_policy = reader.try_read_field("usage_policy")?;
+        let owner_id = reader.try_read_field("owner_id")?;
+
+        Ok((Self {
             id, type_id, x, y, dir, z, size_z, extra, data, expiry_time, usage_policy, owner_id,
-            static_class: if *id < 0 { packet.read() } else { "".to_string()},
+            static_class: if *id < 0 { reader.try_read_field("static_class")? } else { "".to_string()},
             owner_name: None
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1188,7 +1299,7 @@ impl PacketVariable for FloorItem {
             packet.append(self.static_class.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -1270,36 +1381,48 @@ pub enum User {
 }
 
 impl PacketVariable for User {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (id, name, custom, figure, room_index, x, y, z, dir) = packet.read();
+        let id = reader.try_read_field("id")?;
+        let name = reader.try_read_field("name")?;
+        let custom = reader.try_read_field("custom")?;
+        let figure = reader.try_read_field("figure")?;
+        let room_index = reader.try_read_field("room_index")?;
+        let x = reader.try_read_field("x")?;
+        let y = reader.try_read_field("y")?;
+        let z = reader.try_read_field("z")?;
+        let dir = reader.try_read_field("dir")?;
 
-        (match packet.read::<i32>() {
+        Ok((match reader.try_read_field::<i32>("type")? {
             1 => User::Player {
                 id, name, custom, figure, room_index, x, y, z, dir,
-                sex: packet.read(),
-                group_id: packet.read(),
-                group_status: packet.read(),
-                group_name: packet.read(),
-                swim_figure: packet.read(),
-                achievement_score: packet.read(),
-                is_moderator: packet.read()
+                sex: reader.try_read_field("sex")?,
+                group_id: reader.try_read_field("group_id")?,
+                group_status: reader.try_read_field("group_status")?,
+                group_name: reader.try_read_field("group_name")?,
+                swim_figure: reader.try_read_field("swim_figure")?,
+                achievement_score: reader.try_read_field("achievement_score")?,
+                is_moderator: reader.try_read_field("is_moderator")?
             },
             2 => User::Pet {
                 id, name, custom, figure, room_index, x, y, z, dir,
-                sub_type: packet.read(),
-                owner_id: packet.read(),
-                owner_name: packet.read(),
-                rarity_level: packet.read(),
-                has_saddle: packet.read(),
-                is_riding: packet.read(),
-                can_breed: packet.read(),
-                can_harvest: packet.read(),
-                can_revive: packet.read(),
-                has_breeding_permission: packet.read(),
-                pet_level: packet.read(),
-                pet_posture: packet.read()
+                sub_type: reader.try_read_field("sub_type")?,
+                owner_id: reader.try_read_field("owner_id")?,
+                owner_name: reader.try_read_field("owner_name")?,
+                rarity_level: reader.try_read_field("rarity_level")?,
+                has_saddle: reader.try_read_field("has_saddle")?,
+                is_riding: reader.try_read_field("is_riding")?,
+                can_breed: reader.try_read_field("can_breed")?,
+                can_harvest: reader.try_read_field("can_harvest")?,
+                can_revive: reader.try_read_field("can_revive")?,
+                has_breeding_permission: reader.try_read_field("has_breeding_permission")?,
+                pet_level: reader.try_read_field("pet_level")?,
+                pet_posture: reader.try_read_field("pet_posture")?
             },
             3 => User::OldBot {
                 id, name, custom, figure, room_index, x, y, z, dir
@@ -1314,13 +1437,13 @@ impl PacketVariable for User {
                 y,
                 z,
                 dir,
-                sex: packet.read(),
-                owner_id: packet.read(),
-                owner_name: packet.read(),
-                bot_skills: packet.read()
+                sex: reader.try_read_field("sex")?,
+                owner_id: reader.try_read_field("owner_id")?,
+                owner_name: reader.try_read_field("owner_name")?,
+                bot_skills: reader.try_read_field("bot_skills")?
             },
-            _ => panic!("Unknown user type")
-        }, packet.read_index - 6)
+            _ => return Err(DecodeError::new(reader.position(), "Unknown user type"))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1473,8 +1596,12 @@ impl ToString for UserUpdateAction {
 }
 
 impl PacketVariable for Vec<UserUpdateAction> {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let (str, size) = String::from_packet(bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let (str, size) = String::try_from_packet(bytes)?;
 
         let mut actions = Vec::new();
         for s in str.split('/') {
@@ -1486,7 +1613,7 @@ impl PacketVariable for Vec<UserUpdateAction> {
             }
         }
 
-        (actions, size)
+        Ok((actions, size))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1618,13 +1745,17 @@ impl Default for WiredUserMoveType {
 }
 
 impl PacketVariable for WiredUserMoveType {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let (key, size) = i32::from_packet(bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let (key, size) = i32::try_from_packet(bytes)?;
 
         if key == 0 {
-            (Self::Move, size)
+            Ok((Self::Move, size))
         } else {
-            (Self::Slide, size)
+            Ok((Self::Slide, size))
         }
     }
 
@@ -1822,18 +1953,24 @@ pub struct PendingGuideTicket {
 }
 
 impl PacketVariable for PendingGuideTicket {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        let (ticket_type, seconds_ago, is_guide) = packet.read();
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        (Self {
+        let ticket_type = reader.try_read_field("ticket_type")?;
+        let seconds_ago = reader.try_read_field("seconds_ago")?;
+        let is_guide = reader.try_read_field("is_guide")?;
+
+        Ok((Self {
             ticket_type, seconds_ago, is_guide,
-            other_party_name: if ticket_type < 3 || (ticket_type == 3 && !is_guide) { packet.read() } else { "".to_string() },
-            other_party_figure: if ticket_type < 3 || (ticket_type == 3 && !is_guide) { packet.read() } else { "".to_string() },
-            description: if ticket_type == 1 { packet.read() } else { "".to_string() },
-            room_name: if ticket_type == 3 && !is_guide { packet.read() } else { "".to_string() },
-        }, packet.read_index - 6)
+            other_party_name: if ticket_type < 3 || (ticket_type == 3 && !is_guide) { reader.try_read_field("other_party_name")? } else { "".to_string() },
+            other_party_figure: if ticket_type < 3 || (ticket_type == 3 && !is_guide) { reader.try_read_field("other_party_figure")? } else { "".to_string() },
+            description: if ticket_type == 1 { reader.try_read_field("description")? } else { "".to_string() },
+            room_name: if ticket_type == 3 && !is_guide { reader.try_read_field("room_name")? } else { "".to_string() },
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -1854,7 +1991,7 @@ impl PacketVariable for PendingGuideTicket {
             packet.append(self.room_name.clone());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
@@ -2222,75 +2359,79 @@ pub enum SnowWarGameObjectData {
 }
 
 impl PacketVariable for SnowWarGameObjectData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        (match packet.read::<i32>() {
+        Ok((match reader.try_read_field::<i32>("type")? {
             1 => Self::SnowballGameObjectData {
-                id: packet.read(),
-                location_x_3d: packet.read(),
-                location_y_3d: packet.read(),
-                location_z_3d: packet.read(),
-                movement_direction_360: packet.read(),
-                trajectory: packet.read(),
-                time_to_live: packet.read(),
-                throwing_human: packet.read(),
-                parabola_offset: packet.read(),
-                planar_velocity: packet.read()
+                id: reader.try_read_field("id")?,
+                location_x_3d: reader.try_read_field("location_x_3d")?,
+                location_y_3d: reader.try_read_field("location_y_3d")?,
+                location_z_3d: reader.try_read_field("location_z_3d")?,
+                movement_direction_360: reader.try_read_field("movement_direction_360")?,
+                trajectory: reader.try_read_field("trajectory")?,
+                time_to_live: reader.try_read_field("time_to_live")?,
+                throwing_human: reader.try_read_field("throwing_human")?,
+                parabola_offset: reader.try_read_field("parabola_offset")?,
+                planar_velocity: reader.try_read_field("planar_velocity")?
             },
             2 => Self::TreeGameObjectData {
-                id: packet.read(),
-                location_x_3d: packet.read(),
-                location_y_3d: packet.read(),
-                direction: packet.read(),
-                height: packet.read(),
-                fuse_object_id: packet.read(),
-                max_hits: packet.read(),
-                hits: packet.read()
+                id: reader.try_read_field("id")?,
+                location_x_3d: reader.try_read_field("location_x_3d")?,
+                location_y_3d: reader.try_read_field("location_y_3d")?,
+                direction: reader.try_read_field("direction")?,
+                height: reader.try_read_field("height")?,
+                fuse_object_id: reader.try_read_field("fuse_object_id")?,
+                max_hits: reader.try_read_field("max_hits")?,
+                hits: reader.try_read_field("hits")?
             },
             3 => Self::SnowballPileGameObjectData {
-                id: packet.read(),
-                location_x_3d: packet.read(),
-                location_y_3d: packet.read(),
-                max_snow_balls: packet.read(),
-                snowball_count: packet.read(),
-                fuse_object_id: packet.read()
+                id: reader.try_read_field("id")?,
+                location_x_3d: reader.try_read_field("location_x_3d")?,
+                location_y_3d: reader.try_read_field("location_y_3d")?,
+                max_snow_balls: reader.try_read_field("max_snow_balls")?,
+                snowball_count: reader.try_read_field("snowball_count")?,
+                fuse_object_id: reader.try_read_field("fuse_object_id")?
             },
             4 => Self::SnowballMachineGameObjectData {
-                id: packet.read(),
-                location_x_3d: packet.read(),
-                location_y_3d: packet.read(),
-                direction: packet.read(),
-                max_snow_balls: packet.read(),
-                snowball_count: packet.read(),
-                fuse_object_id: packet.read()
+                id: reader.try_read_field("id")?,
+                location_x_3d: reader.try_read_field("location_x_3d")?,
+                location_y_3d: reader.try_read_field("location_y_3d")?,
+                direction: reader.try_read_field("direction")?,
+                max_snow_balls: reader.try_read_field("max_snow_balls")?,
+                snowball_count: reader.try_read_field("snowball_count")?,
+                fuse_object_id: reader.try_read_field("fuse_object_id")?
             },
             5 => Self::HumanGameObjectData {
-                id: packet.read(),
-                current_location_x: packet.read(),
-                current_location_y: packet.read(),
-                current_tile_x: packet.read(),
-                current_tile_y: packet.read(),
-                body_direction: packet.read(),
-                hit_points: packet.read(),
-                snow_ball_count: packet.read(),
-                is_bot: packet.read(),
-                activity_timer: packet.read(),
-                activity_state: packet.read(),
-                next_tile_x: packet.read(),
-                next_tile_y: packet.read(),
-                move_target_x: packet.read(),
-                move_target_y: packet.read(),
-                score: packet.read(),
-                team: packet.read(),
-                user_id: packet.read(),
-                name: packet.read(),
-                mission: packet.read(),
-                figure: packet.read(),
-                sex: packet.read()
+                id: reader.try_read_field("id")?,
+                current_location_x: reader.try_read_field("current_location_x")?,
+                current_location_y: reader.try_read_field("current_location_y")?,
+                current_tile_x: reader.try_read_field("current_tile_x")?,
+                current_tile_y: reader.try_read_field("current_tile_y")?,
+                body_direction: reader.try_read_field("body_direction")?,
+                hit_points: reader.try_read_field("hit_points")?,
+                snow_ball_count: reader.try_read_field("snow_ball_count")?,
+                is_bot: reader.try_read_field("is_bot")?,
+                activity_timer: reader.try_read_field("activity_timer")?,
+                activity_state: reader.try_read_field("activity_state")?,
+                next_tile_x: reader.try_read_field("next_tile_x")?,
+                next_tile_y: reader.try_read_field("next_tile_y")?,
+                move_target_x: reader.try_read_field("move_target_x")?,
+                move_target_y: reader.try_read_field("move_target_y")?,
+                score: reader.try_read_field("score")?,
+                team: reader.try_read_field("team")?,
+                user_id: reader.try_read_field("user_id")?,
+                name: reader.try_read_field("name")?,
+                mission: reader.try_read_field("mission")?,
+                figure: reader.try_read_field("figure")?,
+                sex: reader.try_read_field("sex")?
             },
-            _ => panic!("SnowWarGameObjectData: Unknown type")
-        }, packet.read_index - 6)
+            _ => return Err(DecodeError::new(reader.position(), "SnowWarGameObjectData: Unknown type"))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2364,22 +2505,33 @@ pub struct FurniData {
 }
 
 impl PacketVariable for FurniData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let item_id = packet.read();
-        let item_type: String = packet.read();
-        let (
-            room_item_id, item_type_id, category, stuff_data, is_recyclable, is_tradeable,
-            is_groupable, is_sellable, seconds_to_expiration, has_rent_period_started, flat_id
-        ) = packet.read();
-
-        (Self {
-            slot_id: if item_type.clone() == "S" { packet.read() } else { Default::default() },
-            extra: if item_type.clone() == "S" { packet.read() } else { Default::default() },
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let item_id = reader.try_read_field("item_id")?;
+        let item_type: String = reader.try_read_field("item_type")?;
+        let room_item_id = reader.try_read_field("room_item_id")?;
+        let item_type_id = reader.try_read_field("item_type_id")?;
+        let category = reader.try_read_field("category")?;
+        let stuff_data = reader.try_read_field("stuff_data")?;
+        let is_recyclable = reader.try_read_field("is_recyclable")?;
+        let is_tradeable = reader.try_read_field("is_tradeable")?;
+        let is_groupable = reader.try_read_field("is_groupable")?;
+        let is_sellable = reader.try_read_field("is_sellable")?;
+        let seconds_to_expiration = reader.try_read_field("seconds_to_expiration")?;
+        let has_rent_period_started = reader.try_read_field("has_rent_period_started")?;
+        let flat_id = reader.try_read_field("flat_id")?;
+
+        Ok((Self {
+            slot_id: if item_type.clone() == "S" { reader.try_read_field("slot_id")? } else { Default::default() },
+            extra: if item_type.clone() == "S" { reader.try_read_field("extra")? } else { Default::default() },
             item_id, item_type, room_item_id, item_type_id, category, stuff_data, is_recyclable,
             is_tradeable, is_groupable, is_sellable, seconds_to_expiration, has_rent_period_started,
             flat_id
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2450,19 +2602,29 @@ pub struct ItemDataStructure {
 }
 
 impl PacketVariable for ItemDataStructure {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-
-        let item_id = packet.read();
-        let item_type: String = packet.read();
-        let (room_item_id, item_type_id, category, is_groupable, stuff_data,
-            creation_day, creation_month, creation_year) = packet.read();
-        let extra = if item_type.clone().to_uppercase() == "S" { packet.read() } else { -1 };
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
 
-        (Self {
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+
+        let item_id = reader.try_read_field("item_id")?;
+        let item_type: String = reader.try_read_field("item_type")?;
+        let room_item_id = reader.try_read_field("room_item_id")?;
+        let item_type_id = reader.try_read_field("item_type_id")?;
+        let category = reader.try_read_field("category")?;
+        let is_groupable = reader.try_read_field("is_groupable")?;
+        let stuff_data = reader.try_read_field("stuff_data")?;
+        let creation_day = reader.try_read_field("creation_day")?;
+        let creation_month = reader.try_read_field("creation_month")?;
+        let creation_year = reader.try_read_field("creation_year")?;
+        let extra = if item_type.clone().to_uppercase() == "S" { reader.try_read_field("extra")? } else { -1 };
+
+        Ok((Self {
             item_id, item_type, room_item_id, item_type_id, category, is_groupable,
             stuff_data, creation_day, creation_month, creation_year, extra
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2609,48 +2771,52 @@ pub enum SnowWarGameEventData {
 }
 
 impl PacketVariable for SnowWarGameEventData {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        (match packet.read::<i32>() {
+        Ok((match reader.try_read_field::<i32>("type")? {
             1 => SnowWarGameEventData::HumanLeftGameEventData {
-                human_game_object_id: packet.read()
+                human_game_object_id: reader.try_read_field("human_game_object_id")?
             },
             2 => SnowWarGameEventData::NewMoveTargetEventData {
-                human_game_object_id: packet.read(),
-                x: packet.read(),
-                y: packet.read()
+                human_game_object_id: reader.try_read_field("human_game_object_id")?,
+                x: reader.try_read_field("x")?,
+                y: reader.try_read_field("y")?
             },
             3 => SnowWarGameEventData::HumanThrowsSnowballAtHumanEventData {
-                human_game_object_id: packet.read(),
-                target_human_game_object_id: packet.read(),
-                trajectory: packet.read()
+                human_game_object_id: reader.try_read_field("human_game_object_id")?,
+                target_human_game_object_id: reader.try_read_field("target_human_game_object_id")?,
+                trajectory: reader.try_read_field("trajectory")?
             },
             4 => SnowWarGameEventData::HumanThrowsSnowballAtPositionEventData {
-                human_game_object_id: packet.read(),
-                target_x: packet.read(),
-                target_y: packet.read(),
-                trajectory: packet.read()
+                human_game_object_id: reader.try_read_field("human_game_object_id")?,
+                target_x: reader.try_read_field("target_x")?,
+                target_y: reader.try_read_field("target_y")?,
+                trajectory: reader.try_read_field("trajectory")?
             },
             7 => SnowWarGameEventData::HumanStartsToMakeASnowballEventData {
-                human_game_object_id: packet.read()
+                human_game_object_id: reader.try_read_field("human_game_object_id")?
             },
             8 => SnowWarGameEventData::CreateSnowballEventData {
-                snow_ball_game_object_id: packet.read(),
-                human_game_object_id: packet.read(),
-                target_x: packet.read(),
-                target_y: packet.read(),
-                trajectory: packet.read()
+                snow_ball_game_object_id: reader.try_read_field("snow_ball_game_object_id")?,
+                human_game_object_id: reader.try_read_field("human_game_object_id")?,
+                target_x: reader.try_read_field("target_x")?,
+                target_y: reader.try_read_field("target_y")?,
+                trajectory: reader.try_read_field("trajectory")?
             },
             11 => SnowWarGameEventData::MachineCreatesSnowballEventData {
-                snow_ball_machine_reference: packet.read()
+                snow_ball_machine_reference: reader.try_read_field("snow_ball_machine_reference")?
             },
             12 => SnowWarGameEventData::HumanGetsSnowballsFromMachineEventData {
-                human_game_object_id: packet.read(),
-                snow_ball_machine_reference: packet.read()
+                human_game_object_id: reader.try_read_field("human_game_object_id")?,
+                snow_ball_machine_reference: reader.try_read_field("snow_ball_machine_reference")?
             },
-            _ => panic!("SnowWarGameEventData: Unknown type")
-        }, packet.read_index - 6)
+            _ => return Err(DecodeError::new(reader.position(), "SnowWarGameEventData: Unknown type"))
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2759,29 +2925,36 @@ pub struct MarketPlaceOffer {
 }
 
 impl PacketVariable for MarketPlaceOffer {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
 
-        let (offer_id, status) = packet.read();
-        let furni_type: i32 = packet.read();
+        let offer_id = reader.try_read_field("offer_id")?;
+        let status = reader.try_read_field("status")?;
+        let furni_type: i32 = reader.try_read_field("furni_type")?;
         let mut furni_id = LegacyId(-1);
         let mut stuff_data = StuffData::default();
         let mut extra_data = "".to_string();
         if furni_type == 1 {
-            furni_id = packet.read();
-            stuff_data = packet.read();
+            furni_id = reader.try_read_field("furni_id")?;
+            stuff_data = reader.try_read_field("stuff_data")?;
         } else if furni_type == 2 {
-            furni_id = packet.read();
-            extra_data = packet.read();
+            furni_id = reader.try_read_field("furni_id")?;
+            extra_data = reader.try_read_field("extra_data")?;
         } else if furni_type == 3 {
-            furni_id = packet.read();
+            furni_id = reader.try_read_field("furni_id")?;
             stuff_data = StuffData::EmptyStuffData {
-                unique_serial_data: packet.read()
+                unique_serial_data: reader.try_read_field("unique_serial_data")?
             }
         }
-        let (price, time_left_minutes, average_price) = packet.read();
+        let price = reader.try_read_field("price")?;
+        let time_left_minutes = reader.try_read_field("time_left_minutes")?;
+        let average_price = reader.try_read_field("average_price")?;
 
-        (Self {
+        Ok((Self {
             offer_id,
             furni_id,
             furni_type,
@@ -2792,7 +2965,7 @@ impl PacketVariable for MarketPlaceOffer {
             time_left_minutes,
             average_price,
             offer_count: -1
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2812,7 +2985,7 @@ impl PacketVariable for MarketPlaceOffer {
             self.price, self.time_left_minutes, self.average_price
         ));
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
 
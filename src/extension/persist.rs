@@ -0,0 +1,46 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::error::{Error, Result};
+
+/// Name of the file the globals are saved in, inside the extension's config directory
+pub const STATE_FILE: &str = "state.json";
+
+/// `g-rust/<name>/state.json` in the user's config directory, `$XDG_CONFIG_HOME` (or `~/.config`) on Linux
+///
+/// Characters that aren't allowed in file names are replaced by `_`
+pub fn state_path(name: &str) -> Option<PathBuf> {
+    let name: String = name.chars()
+        .map(| c | if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    dirs::config_dir().map(| dir | dir.join("g-rust").join(name).join(STATE_FILE))
+}
+
+/// Reads the state saved at `path`, `None` if nothing was saved yet
+pub fn load<W: DeserializeOwned>(path: &Path) -> Result<Option<W>> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::State(format!("couldn't read {}: {e}", path.display())))
+    };
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(| e | Error::State(format!("{} doesn't match the globals: {e}", path.display())))
+}
+
+/// Writes `state` to `path`, creating its directory if needed
+///
+/// The state is written to a temporary file first, so a crash while saving doesn't leave a half written file behind
+pub fn save<W: Serialize>(path: &Path, state: &W) -> Result<()> {
+    let json = serde_json::to_string_pretty(state)
+        .map_err(| e | Error::State(format!("couldn't serialize the globals: {e}")))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(| e | Error::State(format!("couldn't create {}: {e}", dir.display())))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)
+        .and_then(| _ | fs::rename(&tmp, path))
+        .map_err(| e | Error::State(format!("couldn't write {}: {e}", path.display())))
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+/// Whether a socket connection with G-Earth is the first one of the extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionKind {
+    New,
+    /// Reconnected after G-Earth closed the previous connection, globals and intercepts are kept
+    Resumed
+}
+
+/// How [`Extension::run`](super::extension::Extension::run) reconnects when the connection with G-Earth is lost
+///
+/// The delay between attempts starts at `initial_delay` and is multiplied by `multiplier` after every failed attempt,
+/// up to `max_delay`. Every attempt connects to the port of the extension's [`PortSource`](super::extension::PortSource)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Gives up after this many failed attempts in a row, `None` keeps trying
+    pub max_attempts: Option<u32>
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_attempts: None
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before reconnect attempt `attempt` (starting at 0), `None` if it shouldn't be attempted
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(| max_attempts | attempt >= max_attempts) {
+            return None;
+        }
+
+        let factor = self.multiplier.max(1.0).powi(attempt.min(i32::MAX as u32) as i32);
+        let delay = self.initial_delay.as_secs_f64() * factor;
+        Some(Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64())))
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+type Task<E> = Box<dyn FnMut(&mut E) + Send>;
+
+/// Handle to a task scheduled with [`Extension::schedule_once`](super::extension::Extension::schedule_once)
+/// or [`Extension::schedule_repeating`](super::extension::Extension::schedule_repeating),
+/// or to a packet queued with [`Extension::queue`](super::extension::Extension::queue)
+///
+/// Can be cloned and cancelled from any thread
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    active: Arc<AtomicBool>
+}
+
+impl TaskHandle {
+    pub(crate) fn new() -> Self {
+        Self {
+            active: Arc::new(AtomicBool::new(true))
+        }
+    }
+
+    /// Stops the task from running (again), does nothing if it already finished
+    pub fn cancel(&self) {
+        self.active.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the task is still scheduled to run
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+}
+
+struct ScheduledTask<E> {
+    due: Instant,
+    interval: Option<Duration>,
+    handle: TaskHandle,
+    task: Task<E>
+}
+
+pub(crate) struct Scheduler<E> {
+    tasks: Vec<ScheduledTask<E>>
+}
+
+impl <E> Default for Scheduler<E> {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::new()
+        }
+    }
+}
+
+impl <E> Scheduler<E> {
+    pub fn schedule(&mut self, delay: Duration, interval: Option<Duration>, task: Task<E>) -> TaskHandle {
+        let handle = TaskHandle::new();
+        self.tasks.push(ScheduledTask {
+            due: Instant::now() + delay,
+            interval,
+            handle: handle.clone(),
+            task
+        });
+        handle
+    }
+
+    pub fn cancel_all(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.handle.cancel();
+        }
+    }
+
+    /// Time until the next task is due, `None` if nothing is scheduled
+    pub fn time_until_next(&mut self) -> Option<Duration> {
+        self.tasks.retain(| task | task.handle.is_active());
+        self.tasks.iter()
+            .map(| task | task.due)
+            .min()
+            .map(| due | due.saturating_duration_since(Instant::now()))
+    }
+
+    /// Runs every task that is due, repeating tasks are rescheduled afterwards
+    ///
+    /// Tasks are taken out of the scheduler while they run, so they can freely schedule new tasks
+    pub fn run_due(ext: &mut E, scheduler: impl Fn(&mut E) -> &mut Self) {
+        let now = Instant::now();
+        let (mut due, pending): (Vec<ScheduledTask<E>>, Vec<ScheduledTask<E>>) = scheduler(ext).tasks
+            .drain(..)
+            .filter(| task | task.handle.is_active())
+            .partition(| task | task.due <= now);
+        scheduler(ext).tasks.extend(pending);
+        due.sort_by_key(| task | task.due);
+
+        for mut task in due {
+            if !task.handle.is_active() {
+                continue;
+            }
+            (task.task)(ext);
+            if let Some(interval) = task.interval {
+                if task.handle.is_active() {
+                    task.due += interval;
+                    if task.due < now {
+                        task.due = now + interval;
+                    }
+                    scheduler(ext).tasks.push(task);
+                }
+            } else {
+                task.handle.cancel();
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use super::scheduler::TaskHandle;
+use crate::error::{Error, Result};
+
+/// Packets the server's flood control applies to, they're held back while the user is muted for flooding
+pub const FLOOD_CONTROLLED_PACKETS: [&str; 3] = ["Chat", "Shout", "Whisper"];
+
+/// Priority of the listener pausing the [`FLOOD_CONTROLLED_PACKETS`] on `FloodControl`, so it runs before any other
+pub const FLOOD_CONTROL_PRIORITY: i32 = i32::MAX;
+
+/// Rate limit of the [`FLOOD_CONTROLLED_PACKETS`] unless set otherwise, kept below what the server tolerates
+pub const DEFAULT_CHAT_RATE_LIMIT: RateLimit = RateLimit {
+    burst: 3,
+    interval: Duration::from_millis(1500)
+};
+
+type SendTask<E> = Box<dyn FnOnce(&mut E) -> Result<()> + Send>;
+type SentCallback<E> = Box<dyn FnOnce(&mut E, Result<()>) + Send>;
+
+/// How often queued packets with the same name may be sent
+///
+/// Up to `burst` packets go out right away, after that one more every `interval`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub interval: Duration
+}
+
+impl RateLimit {
+    pub fn new(burst: u32, interval: Duration) -> Self {
+        Self {
+            burst,
+            interval
+        }
+    }
+}
+
+/// Packets left to send of a rate limit, refilled over time
+struct Budget {
+    tokens: f64,
+    updated: Instant
+}
+
+impl Budget {
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        if !limit.interval.is_zero() {
+            let refilled = now.saturating_duration_since(self.updated).as_secs_f64() / limit.interval.as_secs_f64();
+            self.tokens = (self.tokens + refilled).min(limit.burst.max(1) as f64);
+        } else {
+            self.tokens = limit.burst.max(1) as f64;
+        }
+        self.updated = now;
+    }
+
+    fn wait(&self, limit: &RateLimit) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            limit.interval.mul_f64(1.0 - self.tokens)
+        }
+    }
+}
+
+struct QueuedPacket<E> {
+    name: String,
+    priority: i32,
+    order: u64,
+    handle: TaskHandle,
+    send: SendTask<E>,
+    on_sent: Option<SentCallback<E>>
+}
+
+/// Packets waiting to be sent, see [`Extension::queue`](super::extension::Extension::queue)
+pub(crate) struct SendQueue<E> {
+    packets: Vec<QueuedPacket<E>>,
+    /// Whether the `FloodControl` intercept pausing the queue is registered
+    pub installed: bool,
+    limits: HashMap<String, RateLimit>,
+    budgets: HashMap<String, Budget>,
+    paused_until: Option<Instant>,
+    next_order: u64
+}
+
+impl <E> Default for SendQueue<E> {
+    fn default() -> Self {
+        Self {
+            packets: Vec::new(),
+            installed: false,
+            limits: FLOOD_CONTROLLED_PACKETS.iter()
+                .map(| name | (name.to_string(), DEFAULT_CHAT_RATE_LIMIT))
+                .collect(),
+            budgets: HashMap::new(),
+            paused_until: None,
+            next_order: 0
+        }
+    }
+}
+
+impl <E> SendQueue<E> {
+    pub fn push(&mut self, name: String, priority: i32, send: SendTask<E>, on_sent: Option<SentCallback<E>>) -> TaskHandle {
+        let handle = TaskHandle::new();
+        self.packets.push(QueuedPacket {
+            name,
+            priority,
+            order: self.next_order,
+            handle: handle.clone(),
+            send,
+            on_sent
+        });
+        self.next_order += 1;
+        handle
+    }
+
+    pub fn set_limit(&mut self, name: String, limit: Option<RateLimit>) {
+        self.budgets.remove(&name);
+        match limit {
+            Some(limit) => self.limits.insert(name, limit),
+            None => self.limits.remove(&name)
+        };
+    }
+
+    /// Holds back the [`FLOOD_CONTROLLED_PACKETS`] for `duration`
+    pub fn pause_flood_controlled(&mut self, duration: Duration) {
+        let until = Instant::now() + duration;
+        self.paused_until = Some(self.paused_until.map_or(until, | paused_until | paused_until.max(until)));
+    }
+
+    pub fn pending(&self) -> usize {
+        self.packets.iter().filter(| packet | packet.handle.is_active()).count()
+    }
+
+    /// Time until the next packet can be sent or cancelled packet reported, `None` if nothing is queued
+    pub fn time_until_next(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        if self.packets.iter().any(| packet | !packet.handle.is_active()) {
+            return Some(Duration::ZERO);
+        }
+        let names: Vec<String> = self.packets.iter().map(| packet | packet.name.clone()).collect();
+        names.iter()
+            .map(| name | self.wait(name, now))
+            .min()
+    }
+
+    fn wait(&mut self, name: &str, now: Instant) -> Duration {
+        let paused = match self.paused_until {
+            Some(until) if FLOOD_CONTROLLED_PACKETS.contains(&name) => until.saturating_duration_since(now),
+            _ => Duration::ZERO
+        };
+        let limited = match self.limits.get(name) {
+            Some(limit) => {
+                let budget = self.budgets.entry(name.to_string()).or_insert_with(|| Budget {
+                    tokens: limit.burst.max(1) as f64,
+                    updated: now
+                });
+                budget.refill(limit, now);
+                budget.wait(limit)
+            },
+            None => Duration::ZERO
+        };
+        paused.max(limited)
+    }
+
+    /// Takes out the cancelled packets and the packets that can be sent now, highest priority first and in queueing order otherwise
+    fn take_ready(&mut self) -> Vec<QueuedPacket<E>> {
+        let now = Instant::now();
+        if self.paused_until.is_some_and(| until | until <= now) {
+            self.paused_until = None;
+        }
+
+        let (mut packets, mut ready): (Vec<_>, Vec<_>) = std::mem::take(&mut self.packets).into_iter()
+            .partition(| packet | packet.handle.is_active());
+        packets.sort_by_key(| packet | (Reverse(packet.priority), packet.order));
+
+        for packet in packets {
+            if self.wait(&packet.name, now).is_zero() {
+                if let Some(budget) = self.budgets.get_mut(&packet.name) {
+                    budget.tokens -= 1.0;
+                }
+                ready.push(packet);
+            } else {
+                self.packets.push(packet);
+            }
+        }
+        ready
+    }
+
+    /// Sends every packet that is allowed to go out now and calls their callbacks,
+    /// cancelled packets are dropped with [`Error::Cancelled`]
+    ///
+    /// Packets are taken out of the queue before they're sent, so callbacks can freely queue new packets
+    pub fn run_due(ext: &mut E, queue: impl Fn(&mut E) -> &mut Self) {
+        for packet in queue(ext).take_ready() {
+            let res = if packet.handle.is_active() {
+                let res = (packet.send)(ext);
+                packet.handle.cancel();
+                res
+            } else {
+                Err(Error::Cancelled)
+            };
+            match (packet.on_sent, res) {
+                (Some(on_sent), res) => (on_sent)(ext, res),
+                (None, Err(Error::Cancelled)) => {},
+                (None, Err(e)) => log::error!("Couldn't send queued {}: {e}", packet.name),
+                (None, Ok(())) => {}
+            }
+        }
+    }
+
+    /// Empties the queue, the callbacks of the dropped packets get [`Error::Disconnected`]
+    /// and those of packets cancelled before get [`Error::Cancelled`]
+    pub fn clear(ext: &mut E, queue: impl Fn(&mut E) -> &mut Self) {
+        let packets = std::mem::take(&mut queue(ext).packets);
+        let queue = queue(ext);
+        queue.budgets.clear();
+        queue.paused_until = None;
+        for packet in packets {
+            let error = if packet.handle.is_active() { Error::Disconnected } else { Error::Cancelled };
+            packet.handle.cancel();
+            if let Some(on_sent) = packet.on_sent {
+                (on_sent)(ext, Err(error));
+            }
+        }
+    }
+}
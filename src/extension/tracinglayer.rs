@@ -0,0 +1,209 @@
+use std::fmt::{Debug, Write};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use super::extensionhandle::ExtensionHandle;
+use crate::misc::consoleformat::ConsoleColour;
+
+/// Name of the span [`Extension`](super::extension::Extension) opens around the listeners of every intercepted message,
+/// with the fields `packet`, `direction`, `index` and `header_id`
+pub const INTERCEPT_SPAN: &str = "intercept";
+
+/// [`tracing_subscriber`] layer writing events to the G-Earth extension console
+///
+/// Events are prefixed with the extension name and the spans they happened in, and coloured by level.
+/// Spans taking longer than the slow span threshold are reported as well, so slow intercept listeners show up.
+///
+/// ```no_run
+/// # use g_rust::extension::extension::Extension;
+/// # use g_rust::extension::tracinglayer::GEarthLayer;
+/// use tracing_subscriber::prelude::*;
+///
+/// let mut ext: Extension<()> = Extension::new();
+/// tracing_subscriber::registry().with(GEarthLayer::new(ext.handle())).init();
+/// ext.run().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct GEarthLayer {
+    handle: ExtensionHandle,
+    level: Level,
+    slow_span_threshold: Option<Duration>
+}
+
+/// Default for [`GEarthLayer::with_slow_span_threshold`]
+pub const DEFAULT_SLOW_SPAN_THRESHOLD: Duration = Duration::from_millis(100);
+
+impl GEarthLayer {
+    /// Writes events from [`Level::INFO`] up and spans taking longer than [`DEFAULT_SLOW_SPAN_THRESHOLD`]
+    pub fn new(handle: ExtensionHandle) -> Self {
+        Self {
+            handle,
+            level: Level::INFO,
+            slow_span_threshold: Some(DEFAULT_SLOW_SPAN_THRESHOLD)
+        }
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Reports spans that were entered for longer than `threshold` in total, `None` disables it
+    pub fn with_slow_span_threshold(mut self, threshold: Option<Duration>) -> Self {
+        self.slow_span_threshold = threshold;
+        self
+    }
+
+    pub fn colour(level: &Level) -> ConsoleColour {
+        match *level {
+            Level::ERROR => ConsoleColour::Red,
+            Level::WARN => ConsoleColour::Orange,
+            Level::INFO => ConsoleColour::White,
+            Level::DEBUG => ConsoleColour::Grey,
+            Level::TRACE => ConsoleColour::LightGrey
+        }
+    }
+
+    fn span_prefix<S: Subscriber + for<'a> LookupSpan<'a>>(ctx: &Context<'_, S>, event: &Event<'_>) -> String {
+        let mut res = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                let _ = write!(res, "{}", span.name());
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    if !fields.0.is_empty() {
+                        let _ = write!(res, "{{{}}}", fields.0);
+                    }
+                }
+                res += ": ";
+            }
+        }
+        res
+    }
+}
+
+/// Formatted fields of a span, stored in its extensions
+struct SpanFields(String);
+
+/// Time spent inside a span, stored in its extensions
+struct SpanTiming {
+    busy: Duration,
+    entered: Option<Instant>
+}
+
+/// Formats fields as `key=value`, the `message` field goes first without its key
+#[derive(Default)]
+struct FieldFormatter {
+    message: String,
+    fields: String
+}
+
+impl FieldFormatter {
+    fn finish(self) -> String {
+        match (self.message.is_empty(), self.fields.is_empty()) {
+            (_, true) => self.message,
+            (true, false) => self.fields,
+            (false, false) => format!("{} {}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for FieldFormatter {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+            let _ = write!(self.fields, "{}={value:?}", field.name());
+        }
+    }
+}
+
+impl <S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for GEarthLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = FieldFormatter::default();
+        attrs.record(&mut fields);
+        let mut extensions = span.extensions_mut();
+        extensions.insert(SpanFields(fields.finish()));
+        extensions.insert(SpanTiming {
+            busy: Duration::ZERO,
+            entered: None
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut formatter = FieldFormatter {
+                message: String::new(),
+                fields: std::mem::take(fields)
+            };
+            values.record(&mut formatter);
+            *fields = formatter.finish();
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let level = event.metadata().level();
+        if *level > self.level {
+            return;
+        }
+
+        let mut fields = FieldFormatter::default();
+        event.record(&mut fields);
+        let line = Self::span_prefix(&ctx, event) + &fields.finish();
+        let _ = self.handle.write_to_console_buffered(line, Self::colour(level));
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timing) = span.extensions_mut().get_mut::<SpanTiming>() {
+                timing.entered = Some(Instant::now());
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timing) = span.extensions_mut().get_mut::<SpanTiming>() {
+                if let Some(entered) = timing.entered.take() {
+                    timing.busy += entered.elapsed();
+                }
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let (Some(threshold), Some(span)) = (self.slow_span_threshold, ctx.span(&id)) else {
+            return;
+        };
+        let extensions = span.extensions();
+        let Some(timing) = extensions.get::<SpanTiming>() else {
+            return;
+        };
+        if timing.busy >= threshold {
+            let fields = extensions.get::<SpanFields>().map(| fields | fields.0.as_str()).unwrap_or("");
+            let line = format!("{}{{{fields}}} took {} ms", span.name(), timing.busy.as_millis());
+            let _ = self.handle.write_to_console_buffered(line, ConsoleColour::Orange);
+        }
+    }
+}
@@ -3,7 +3,12 @@ extern crate packetvar_derive;
 #[macro_use]
 extern crate parser_derive;
 
+pub mod error;
 pub mod protocol;
 pub mod extension;
 pub mod misc;
 pub mod services;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use error::{DecodeError, Error, Result};
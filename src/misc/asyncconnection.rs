@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::Mutex;
+use super::messages::OutgoingMessageIds;
+use super::consoleformat::ConsoleColour;
+use crate::error::{Error, Result};
+use crate::protocol::hpacket::HPacket;
+use crate::protocol::vars::packetvariable::PacketVariable;
+
+#[derive(Debug)]
+pub(crate) struct AsyncGEarthConnection {
+    reader: OwnedReadHalf,
+    writer: AsyncGEarthWriter
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct AsyncGEarthWriter {
+    socket: Arc<Mutex<OwnedWriteHalf>>
+}
+
+impl AsyncGEarthConnection {
+    pub async fn new(port: String) -> Result<Self> {
+        let con = TcpStream::connect(format!("127.0.0.1:{port}")).await.map_err(Error::Connection)?;
+        con.set_nodelay(true).map_err(Error::Connection)?;
+        let (reader, writer) = con.into_split();
+        Ok(AsyncGEarthConnection {
+            reader,
+            writer: AsyncGEarthWriter {
+                socket: Arc::new(Mutex::new(writer))
+            }
+        })
+    }
+
+    pub fn writer(&self) -> AsyncGEarthWriter {
+        self.writer.clone()
+    }
+
+    pub async fn read_packet(&mut self) -> Result<HPacket> {
+        let mut bytes = vec![0u8; 4];
+        self.reader.read_exact(&mut bytes).await?;
+        let length = i32::from_packet(&bytes).0;
+        if length < 2 {
+            return Err(Error::Framing(length));
+        }
+        let mut body = vec![0u8; length as usize];
+        self.reader.read_exact(&mut body).await?;
+        bytes.append(&mut body);
+        Ok(HPacket::from_bytes(bytes))
+    }
+}
+
+impl AsyncGEarthWriter {
+    pub async fn write(&self, bytes: Vec<u8>) -> Result<()> {
+        self.socket.lock().await.write_all(&bytes[..]).await.map_err(Error::Connection)
+    }
+
+    pub async fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) -> Result<()> {
+        let mut packet = HPacket::from_header_id(OutgoingMessageIds::EXTENSION_CONSOLE_LOG);
+        packet.append(format!("[{colour}] {s}"));
+        self.write(packet.get_bytes()).await
+    }
+}
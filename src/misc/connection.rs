@@ -1,55 +1,80 @@
-use std::net::TcpStream;
-use std::fmt::{Debug, Error};
+use std::fmt::{Debug, Formatter};
 use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
 use super::messages::OutgoingMessageIds;
 use super::consoleformat::ConsoleColour;
+use super::transport::{Transport, TransportReader, TransportWriter};
+use crate::error::{Error, Result};
 use crate::protocol::hpacket::HPacket;
+use crate::protocol::vars::packetvariable::PacketVariable;
 
-#[derive(Debug)]
 pub(crate) struct GEarthConnection {
-    socket: Box<TcpStream>
+    reader: TransportReader,
+    writer: GEarthWriter
 }
 
-impl Clone for GEarthConnection {
-    fn clone(&self) -> Self {
-        let socket = self.socket.try_clone().expect("Couldn't clone extension");
-        GEarthConnection {
-            socket: Box::new(socket)
-        }
+#[derive(Clone)]
+pub(crate) struct GEarthWriter {
+    socket: Arc<Mutex<TransportWriter>>
+}
+
+impl Debug for GEarthConnection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GEarthConnection").finish_non_exhaustive()
+    }
+}
+
+impl Debug for GEarthWriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GEarthWriter").finish_non_exhaustive()
     }
 }
 
 impl GEarthConnection {
-    pub fn new(port: String) -> Self {
-        let con = TcpStream::connect(format!("127.0.0.1:{port}")).expect("Couldn't connect to G-Earth...");
-        con.set_nodelay(true).expect("Couldn't set \"nodelay\" to true...");
-        GEarthConnection {
-            socket: Box::new(con)
-        }
+    pub fn new(transport: Box<dyn Transport>) -> Result<Self> {
+        let (reader, writer) = transport.split().map_err(Error::Connection)?;
+        Ok(GEarthConnection {
+            reader,
+            writer: GEarthWriter {
+                socket: Arc::new(Mutex::new(writer))
+            }
+        })
+    }
+
+    pub fn writer(&self) -> GEarthWriter {
+        self.writer.clone()
     }
 
-    pub fn read(self, length: u64) -> Result<Vec<u8>, Error> {
-        let mut take = self.socket.take(length);
-        let mut bytes = Vec::new();
-        let res = take.read_to_end(&mut bytes);
-        if res.is_ok() && res.unwrap() == length as usize {
-            Ok(bytes)
-        } else {
-            Err(Error)
+    pub fn read(&mut self, length: usize) -> Result<Vec<u8>> {
+        let mut bytes = vec![0u8; length];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn read_packet(&mut self) -> Result<HPacket> {
+        let mut bytes = self.read(4)?;
+        let length = i32::from_packet(&bytes).0;
+        if length < 2 {
+            return Err(Error::Framing(length));
         }
+        bytes.append(&mut self.read(length as usize)?);
+        Ok(HPacket::from_bytes(bytes))
     }
+}
 
-    pub fn write(mut self, bytes: Vec<u8>) {
-        self.socket.write(&bytes[..]).expect("Couldn't write to socket");
+impl GEarthWriter {
+    pub fn write(&self, bytes: &[u8]) -> Result<()> {
+        let mut socket = self.socket.lock().unwrap();
+        socket.write_all(bytes).and_then(| _ | socket.flush()).map_err(Error::Connection)
     }
 
-    pub fn write_to_console(self, s: String) {
+    pub fn write_to_console(&self, s: String) -> Result<()> {
         self.write_to_console_formatted(s, ConsoleColour::White)
     }
 
-    pub fn write_to_console_formatted(self, s: String, colour: ConsoleColour) {
+    pub fn write_to_console_formatted(&self, s: String, colour: ConsoleColour) -> Result<()> {
         let mut packet = HPacket::from_header_id(OutgoingMessageIds::EXTENSION_CONSOLE_LOG);
         packet.append(format!("[{colour}] {s}"));
-        self.write(packet.get_bytes());
+        self.write(packet.bytes())
     }
 }
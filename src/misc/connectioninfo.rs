@@ -1,6 +1,5 @@
 use crate::misc::hclient::HClient;
 use crate::protocol::vars::packetvariable::PacketVariable;
-use crate::protocol::hpacket::HPacket;
 
 #[derive(Clone, Eq, PartialEq, PacketVariable, Debug)]
 pub struct ConnectionInfo {
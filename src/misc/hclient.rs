@@ -1,6 +1,7 @@
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 
 pub(crate) static CUR_CLIENT: Lazy<Mutex<HClient>> = Lazy::new(| | Mutex::new(HClient::Undefined));
 
@@ -13,14 +14,18 @@ pub enum HClient {
 }
 
 impl PacketVariable for HClient {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let (key, size) = String::from_packet(bytes);
-        (match key.as_str() {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let (key, size) = String::try_from_packet(bytes)?;
+        Ok((match key.as_str() {
             "UNITY" => HClient::Unity,
             "FLASH" => HClient::Flash,
             "NITRO" => HClient::Nitro,
             _ => HClient::Undefined
-        }, size)
+        }, size))
     }
 
     fn to_packet(&self) -> Vec<u8> {
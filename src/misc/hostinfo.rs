@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use crate::protocol::hpacket::HPacket;
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct HostInfo {
     pub packet_logger: String,
     pub version: String,
@@ -10,20 +12,24 @@ pub struct HostInfo {
 }
 
 impl PacketVariable for HostInfo {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let (packet_logger, version) = packet.read();
-        let c: i32 = packet.read();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let (packet_logger, version) = reader.try_read()?;
+        let c: i32 = reader.try_read_field("c")?;
         let mut attributes = HashMap::new();
         for _ in 0..c {
-            attributes.insert(packet.read(), packet.read());
+            attributes.insert(reader.try_read()?, reader.try_read()?);
         }
 
-        (HostInfo {
+        Ok((HostInfo {
             packet_logger,
             version,
             attributes
-        }, packet.read_index - 6)
+        }, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -33,6 +39,6 @@ impl PacketVariable for HostInfo {
             packet.append((key.to_string(), value.to_string()));
         }
 
-        packet.get_bytes()[6..].to_vec()
+        packet.into_body()
     }
 }
\ No newline at end of file
@@ -3,4 +3,8 @@ pub mod hostinfo;
 pub mod connectioninfo;
 pub mod consoleformat;
 pub(crate) mod connection;
+pub mod transport;
 pub(crate) mod messages;
+
+#[cfg(feature = "tokio")]
+pub(crate) mod asyncconnection;
\ No newline at end of file
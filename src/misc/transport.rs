@@ -0,0 +1,152 @@
+use std::fmt::Debug;
+use std::io;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+use crate::error::{Error, Result};
+
+pub type TransportReader = Box<dyn Read + Send>;
+pub type TransportWriter = Box<dyn Write + Send>;
+
+/// Byte stream carrying the extension protocol between an extension and G-Earth
+///
+/// Implemented for [`TcpStream`] (what [`Extension::run`](crate::extension::extension::Extension::run) uses),
+/// [`UnixStream`] and the in-process [`ChannelTransport`]
+pub trait Transport: Debug + Send {
+    /// Splits the transport in a reading and a writing half, which are used from different threads
+    fn split(self: Box<Self>) -> io::Result<(TransportReader, TransportWriter)>;
+}
+
+impl Transport for TcpStream {
+    fn split(self: Box<Self>) -> io::Result<(TransportReader, TransportWriter)> {
+        Ok((Box::new(self.try_clone()?), self))
+    }
+}
+
+#[cfg(unix)]
+impl Transport for UnixStream {
+    fn split(self: Box<Self>) -> io::Result<(TransportReader, TransportWriter)> {
+        Ok((Box::new(self.try_clone()?), self))
+    }
+}
+
+/// Connects to G-Earth listening on `port` on this machine
+pub fn connect_tcp(port: &str) -> Result<TcpStream> {
+    let con = TcpStream::connect(format!("127.0.0.1:{port}")).map_err(Error::Connection)?;
+    con.set_nodelay(true).map_err(Error::Connection)?;
+    Ok(con)
+}
+
+/// In-process transport, bytes written to one end of a [`pair`](Self::pair) can be read from the other
+///
+/// Reading returns end of file once the other end is dropped
+#[derive(Debug)]
+pub struct ChannelTransport {
+    reader: ChannelReader,
+    writer: ChannelWriter
+}
+
+#[derive(Debug)]
+pub struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+    timeout: Option<Duration>
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelWriter {
+    sender: Sender<Vec<u8>>
+}
+
+impl ChannelTransport {
+    /// Creates two connected ends, e.g. one for the extension and one for the code acting as G-Earth
+    pub fn pair() -> (ChannelTransport, ChannelTransport) {
+        let (sender_a, receiver_a) = mpsc::channel();
+        let (sender_b, receiver_b) = mpsc::channel();
+        (ChannelTransport::new(receiver_a, sender_b), ChannelTransport::new(receiver_b, sender_a))
+    }
+
+    fn new(receiver: Receiver<Vec<u8>>, sender: Sender<Vec<u8>>) -> Self {
+        ChannelTransport {
+            reader: ChannelReader {
+                receiver,
+                buffer: Vec::new(),
+                position: 0,
+                timeout: None
+            },
+            writer: ChannelWriter {
+                sender
+            }
+        }
+    }
+
+    /// Makes reads fail with [`TimedOut`](io::ErrorKind::TimedOut) when nothing arrives in time, `None` waits forever
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.reader.timeout = timeout;
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn split(self: Box<Self>) -> io::Result<(TransportReader, TransportWriter)> {
+        Ok((Box::new(self.reader), Box::new(self.writer)))
+    }
+}
+
+impl Read for ChannelTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Write for ChannelTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() {
+            let received = match self.timeout {
+                Some(timeout) => self.receiver.recv_timeout(timeout),
+                None => self.receiver.recv().map_err(| _ | RecvTimeoutError::Disconnected)
+            };
+            match received {
+                Ok(bytes) => {
+                    self.buffer = bytes;
+                    self.position = 0;
+                },
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::TimedOut.into()),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0)
+            }
+        }
+
+        let length = buf.len().min(self.buffer.len() - self.position);
+        buf[..length].copy_from_slice(&self.buffer[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.sender.send(buf.to_vec()).map_err(| _ | io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt::{Display, Formatter};
+use crate::error::{DecodeError, Error, Result};
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+use super::hdirection::HDirection;
+use super::hpacket::HPacket;
+use super::packetreader::PacketReader;
+use super::vars::longstring::LongString;
+use super::vars::packetvariable::PacketVariable;
+
+/// Type of a value in a packet structure, written as one character in `PacketInfo.structure`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DynType {
+    /// `i`
+    Int,
+    /// `s`
+    String,
+    /// `S`, a string with a 4 byte length
+    LongString,
+    /// `b`
+    Bool,
+    /// `B`
+    Byte,
+    /// `u`
+    Short,
+    /// `l`
+    Long,
+    /// `d`
+    Double,
+    /// `f`
+    Float
+}
+
+impl DynType {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'i' => Some(DynType::Int),
+            's' => Some(DynType::String),
+            'S' => Some(DynType::LongString),
+            'b' => Some(DynType::Bool),
+            'B' => Some(DynType::Byte),
+            'u' => Some(DynType::Short),
+            'l' => Some(DynType::Long),
+            'd' => Some(DynType::Double),
+            'f' => Some(DynType::Float),
+            _ => None
+        }
+    }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            DynType::Int => 'i',
+            DynType::String => 's',
+            DynType::LongString => 'S',
+            DynType::Bool => 'b',
+            DynType::Byte => 'B',
+            DynType::Short => 'u',
+            DynType::Long => 'l',
+            DynType::Double => 'd',
+            DynType::Float => 'f'
+        }
+    }
+
+    /// Parses a structure like `"iis"` or `"i,i,s"`, commas and whitespace are ignored
+    pub fn parse_structure(structure: &str) -> Result<Vec<DynType>> {
+        structure.chars()
+            .filter(| c | *c != ',' && !c.is_whitespace())
+            .map(| c | DynType::from_char(c).ok_or_else(|| Error::Encoding(format!("unknown structure type '{c}'"))))
+            .collect()
+    }
+}
+
+/// A packet value of a type only known at runtime
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynValue {
+    Int(i32),
+    String(String),
+    LongString(String),
+    Bool(bool),
+    Byte(u8),
+    Short(u16),
+    Long(i64),
+    Double(f64),
+    Float(f32)
+}
+
+impl DynValue {
+    pub fn get_type(&self) -> DynType {
+        match self {
+            DynValue::Int(_) => DynType::Int,
+            DynValue::String(_) => DynType::String,
+            DynValue::LongString(_) => DynType::LongString,
+            DynValue::Bool(_) => DynType::Bool,
+            DynValue::Byte(_) => DynType::Byte,
+            DynValue::Short(_) => DynType::Short,
+            DynValue::Long(_) => DynType::Long,
+            DynValue::Double(_) => DynType::Double,
+            DynValue::Float(_) => DynType::Float
+        }
+    }
+
+    /// Reads a value of the given type and moves the reader past it
+    pub fn read(reader: &mut PacketReader, dyn_type: DynType) -> std::result::Result<Self, DecodeError> {
+        Ok(match dyn_type {
+            DynType::Int => DynValue::Int(reader.try_read()?),
+            DynType::String => DynValue::String(reader.try_read()?),
+            DynType::LongString => DynValue::LongString(reader.try_read::<LongString>()?.0),
+            DynType::Bool => DynValue::Bool(reader.try_read()?),
+            DynType::Byte => DynValue::Byte(reader.try_read()?),
+            DynType::Short => DynValue::Short(reader.try_read()?),
+            DynType::Long => DynValue::Long(reader.try_read()?),
+            DynType::Double => DynValue::Double(reader.try_read()?),
+            DynType::Float => DynValue::Float(reader.try_read()?)
+        })
+    }
+
+    /// Reads one value per type in `structure` from the start of `bytes`
+    pub fn read_all(bytes: &[u8], structure: &[DynType]) -> std::result::Result<Vec<Self>, DecodeError> {
+        let mut reader = PacketReader::new(bytes);
+        structure.iter().enumerate()
+            .map(| (i, dyn_type) | DynValue::read(&mut reader, *dyn_type).map_err(| e | e.in_field(format!("[{i}]"))))
+            .collect()
+    }
+
+    pub fn to_packet(&self) -> Vec<u8> {
+        match self {
+            DynValue::Int(v) => v.to_packet(),
+            DynValue::String(v) => v.to_packet(),
+            DynValue::LongString(v) => LongString(v.clone()).to_packet(),
+            DynValue::Bool(v) => v.to_packet(),
+            DynValue::Byte(v) => v.to_packet(),
+            DynValue::Short(v) => v.to_packet(),
+            DynValue::Long(v) => v.to_packet(),
+            DynValue::Double(v) => v.to_packet(),
+            DynValue::Float(v) => v.to_packet()
+        }
+    }
+}
+
+impl Display for DynValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DynValue::Int(v) => write!(f, "{v}"),
+            DynValue::String(v) | DynValue::LongString(v) => write!(f, "{v:?}"),
+            DynValue::Bool(v) => write!(f, "{v}"),
+            DynValue::Byte(v) => write!(f, "{v}"),
+            DynValue::Short(v) => write!(f, "{v}"),
+            DynValue::Long(v) => write!(f, "{v}"),
+            DynValue::Double(v) => write!(f, "{v:?}"),
+            DynValue::Float(v) => write!(f, "{v:?}")
+        }
+    }
+}
+
+/// A packet read according to a structure instead of a hand-written parser
+///
+/// The values can be edited and written back with [`to_packet`](Self::to_packet)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicPacket {
+    pub header_id: i16,
+    /// Name from the packet info, if the packet was read with [`read_with_packet_info`](Self::read_with_packet_info)
+    pub name: Option<String>,
+    pub direction: HDirection,
+    pub values: Vec<DynValue>,
+    /// Bytes left after the structure, written back unchanged
+    pub rest: Vec<u8>
+}
+
+impl DynamicPacket {
+    /// Reads the packet's body according to `structure`, e.g. `"iis"`
+    pub fn read(packet: &HPacket, structure: &str) -> Result<Self> {
+        let structure = DynType::parse_structure(structure)?;
+        let bytes = packet.bytes();
+        let body = &bytes[6.min(bytes.len())..];
+        let values = DynValue::read_all(body, &structure)?;
+        let read: usize = values.iter().map(| value | value.to_packet().len()).sum();
+
+        Ok(Self {
+            header_id: packet.header_id(),
+            name: None,
+            direction: packet.identifier_direction.clone(),
+            values,
+            rest: body[read..].to_vec()
+        })
+    }
+
+    /// Reads the packet according to the structure G-Earth knows for its header id
+    pub fn read_with_packet_info(packet: &HPacket, direction: HDirection, manager: &mut PacketInfoManager) -> Result<Self> {
+        let header_id = packet.header_id();
+        let packet_info = manager.get_packet_info_from_header_id(direction.clone(), header_id as i32)
+            .ok_or_else(|| Error::UnknownPacket { direction: direction.clone(), name: header_id.to_string() })?;
+
+        let mut res = Self::read(packet, &packet_info.structure)?;
+        res.name = Some(packet_info.name);
+        res.direction = direction;
+        Ok(res)
+    }
+
+    pub fn structure(&self) -> String {
+        self.values.iter().map(| value | value.get_type().to_char()).collect()
+    }
+
+    pub fn to_packet(&self) -> HPacket {
+        let mut bytes: Vec<u8> = self.values.iter().flat_map(| value | value.to_packet()).collect();
+        bytes.extend(self.rest.iter());
+        HPacket::from_header_id_and_bytes(self.header_id, bytes)
+    }
+}
+
+/// Annotated dump with one line per value and its offset in the body
+impl Display for DynamicPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            HDirection::ToClient => "in",
+            HDirection::ToServer => "out",
+            HDirection::None => "?"
+        };
+        writeln!(f, "{} ({direction}, header {}, structure \"{}\")", self.name.as_deref().unwrap_or("Unknown"), self.header_id, self.structure())?;
+
+        let mut offset = 0;
+        for (i, value) in self.values.iter().enumerate() {
+            writeln!(f, "  [{i}] {} @{offset}: {value}", value.get_type().to_char())?;
+            offset += value.to_packet().len();
+        }
+        if !self.rest.is_empty() {
+            writeln!(f, "  {} unread bytes @{offset}: {:?}", self.rest.len(), self.rest)?;
+        }
+        Ok(())
+    }
+}
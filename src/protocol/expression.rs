@@ -0,0 +1,158 @@
+use std::str::FromStr;
+use crate::error::{Error, Result};
+use super::dynamicpacket::{DynamicPacket, DynValue};
+use super::hdirection::HDirection;
+use super::hpacket::HPacket;
+use super::vars::packetvariable::PacketVariable;
+
+fn invalid(position: usize, reason: impl Into<String>) -> Error {
+    Error::Encoding(format!("invalid packet expression at {position}: {}", reason.into()))
+}
+
+fn parse_number<T: FromStr>(position: usize, value: &str) -> Result<T> {
+    value.trim().parse().map_err(| _ | invalid(position, format!("\"{value}\" is not a valid {}", std::any::type_name::<T>())))
+}
+
+/// Reads a string value up to its closing quote, returns it and the length of the value including quotes
+fn parse_string(position: usize, value: &str) -> Result<(String, usize)> {
+    let mut chars = value.char_indices();
+    if chars.next() != Some((0, '"')) {
+        return Err(invalid(position, "expected a quoted string"));
+    }
+
+    let mut res = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((res, i + 1)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => res.push('\n'),
+                Some((_, 'r')) => res.push('\r'),
+                Some((_, 't')) => res.push('\t'),
+                Some((_, c)) => res.push(c),
+                None => break
+            },
+            c => res.push(c)
+        }
+    }
+    Err(invalid(position, "unterminated string"))
+}
+
+/// Parses an expression like `{out:Chat}{s:"hi"}{i:0}{i:0}` or `{h:1234}{b:true}{l:5}`
+pub(crate) fn parse(expression: &str) -> Result<HPacket> {
+    let mut packet: Option<HPacket> = None;
+    let mut body: Vec<u8> = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let rest = &expression[position..];
+        let trimmed = rest.trim_start();
+        position += rest.len() - trimmed.len();
+        if trimmed.is_empty() {
+            break;
+        }
+        if !trimmed.starts_with('{') {
+            return Err(invalid(position, "expected '{'"));
+        }
+        let colon = trimmed.find(':').ok_or_else(|| invalid(position, "expected ':'"))?;
+        let kind = &trimmed[1..colon];
+        let value_position = position + colon + 1;
+        let value = &trimmed[colon + 1..];
+
+        let value_length = if kind == "s" {
+            let (s, length) = parse_string(value_position, value)?;
+            body.extend(s.to_packet());
+            length
+        } else {
+            let length = value.find('}').ok_or_else(|| invalid(value_position, "expected '}'"))?;
+            let value = &value[..length];
+            match kind {
+                "h" | "in" | "out" if packet.is_some() || !body.is_empty() =>
+                    return Err(invalid(position, "the header has to come first")),
+                "h" => packet = Some(HPacket::from_header_id(parse_number(value_position, value)?)),
+                "in" => packet = Some(HPacket::from_identifier_and_direction(value.trim().to_string(), HDirection::ToClient)),
+                "out" => packet = Some(HPacket::from_identifier_and_direction(value.trim().to_string(), HDirection::ToServer)),
+                "i" => body.extend(parse_number::<i32>(value_position, value)?.to_packet()),
+                "l" => body.extend(parse_number::<i64>(value_position, value)?.to_packet()),
+                "d" => body.extend(parse_number::<f64>(value_position, value)?.to_packet()),
+                "u" => body.extend(match parse_number::<u16>(value_position, value) {
+                    Ok(short) => short.to_packet(),
+                    Err(_) => parse_number::<i16>(value_position, value)?.to_packet()
+                }),
+                "b" => body.push(match value.trim() {
+                    "true" => 1,
+                    "false" => 0,
+                    byte => match parse_number::<u8>(value_position, byte) {
+                        Ok(byte) => byte,
+                        Err(_) => parse_number::<i8>(value_position, byte)? as u8
+                    }
+                }),
+                kind => return Err(invalid(position, format!("unknown type \"{kind}\"")))
+            }
+            length
+        };
+
+        let end = value_position + value_length;
+        if !expression[end..].starts_with('}') {
+            return Err(invalid(end, "expected '}'"));
+        }
+        position = end + 1;
+    }
+
+    let mut packet = packet.ok_or_else(|| invalid(0, "missing {h:..}, {in:..} or {out:..} header"))?;
+    if !body.is_empty() {
+        packet.append_bytes(body);
+    }
+    Ok(packet)
+}
+
+fn format_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push(c)
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn format_value(value: &DynValue) -> Result<String> {
+    Ok(match value {
+        DynValue::Int(v) => format!("{{i:{v}}}"),
+        DynValue::String(v) => format!("{{s:{}}}", format_string(v)),
+        DynValue::Bool(v) => format!("{{b:{v}}}"),
+        DynValue::Byte(v) => format!("{{b:{v}}}"),
+        DynValue::Short(v) => format!("{{u:{v}}}"),
+        DynValue::Long(v) => format!("{{l:{v}}}"),
+        DynValue::Double(v) => format!("{{d:{v:?}}}"),
+        value => return Err(Error::Encoding(format!("structure type '{}' has no expression syntax", value.get_type().to_char())))
+    })
+}
+
+/// Formats the packet as an expression, reading the body as described by `structure`
+///
+/// The structure has one character per value (`i`, `s`, `b`, `B`, `u`, `l` or `d`), commas are ignored.
+/// Anything left after the structure is written as `{b:..}` bytes.
+pub(crate) fn format(packet: &HPacket, structure: &str) -> Result<String> {
+    let mut res = if packet.identifier.is_empty() || packet.identifier_direction == HDirection::None {
+        format!("{{h:{}}}", packet.header_id())
+    } else if packet.identifier_direction == HDirection::ToClient {
+        format!("{{in:{}}}", packet.identifier)
+    } else {
+        format!("{{out:{}}}", packet.identifier)
+    };
+
+    let dynamic = DynamicPacket::read(packet, structure)?;
+    for value in dynamic.values.iter() {
+        res += &format_value(value)?;
+    }
+    for byte in dynamic.rest {
+        res += &format!("{{b:{byte}}}");
+    }
+    Ok(res)
+}
@@ -1,6 +1,7 @@
 use std::convert::Infallible;
 use std::str::FromStr;
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum HDirection {
@@ -40,11 +41,15 @@ impl ToString for HDirection {
 }
 
 impl PacketVariable for HDirection {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        (
-            if bool::from_packet(bytes).0 { HDirection::ToServer } else { HDirection::ToClient },
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        Ok((
+            if bool::try_from_packet(bytes)?.0 { HDirection::ToServer } else { HDirection::ToClient },
             1
-        )
+        ))
     }
 
     fn to_packet(&self) -> Vec<u8> {
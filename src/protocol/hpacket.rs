@@ -1,7 +1,10 @@
 use encoding::all::ISO_8859_1;
 use encoding::{DecoderTrap, EncoderTrap, Encoding};
+use crate::error::{DecodeError, Error};
 use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+use super::expression;
 use super::hdirection::HDirection;
+use super::packetreader::PacketReader;
 use super::vars::packetvariable::PacketVariable;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -29,14 +32,14 @@ impl Default for HPacket {
 impl HPacket {
     pub fn from_bytes(bytes: Vec<u8>) -> Self {
         let mut res = HPacket::default();
-        res.packet_in_bytes = bytes.clone();
+        res.packet_in_bytes = bytes;
         res.fix_length();
         res
     }
 
     pub fn from_packet(packet: HPacket) -> Self {
         let mut res = HPacket::default();
-        res.packet_in_bytes = packet.packet_in_bytes.clone();
+        res.packet_in_bytes = packet.packet_in_bytes;
         res.is_edited = packet.is_edited;
         res
     }
@@ -69,6 +72,44 @@ impl HPacket {
         res
     }
 
+    /// Builds a packet from G-Earth's expression syntax, e.g. `{out:Chat}{s:"hi"}{i:0}{i:0}` or `{h:1234}{b:true}`
+    ///
+    /// `{in:Name}` and `{out:Name}` headers are resolved when the packet is sent,
+    /// like with [`from_identifier_and_direction`](Self::from_identifier_and_direction)
+    pub fn from_expression(expression: &str) -> crate::error::Result<HPacket> {
+        expression::parse(expression)
+    }
+
+    /// Same as [`from_expression`](Self::from_expression), resolves `{in:Name}` and `{out:Name}` headers right away
+    pub fn from_expression_with_packet_info(expression: &str, manager: &PacketInfoManager) -> crate::error::Result<HPacket> {
+        let mut packet = Self::from_expression(expression)?;
+        if packet.identifier_direction != HDirection::None {
+            if !packet.can_complete(manager.clone()) {
+                return Err(Error::UnknownPacket {
+                    direction: packet.identifier_direction.clone(),
+                    name: packet.identifier.clone()
+                });
+            }
+            packet.complete_packet(manager.clone());
+        }
+        Ok(packet)
+    }
+
+    /// Formats the packet in G-Earth's expression syntax, with the body as `{b:..}` bytes
+    ///
+    /// Use [`to_expression_with_structure`](Self::to_expression_with_structure) to get typed values
+    pub fn to_expression(&self) -> String {
+        expression::format(self, "").expect("Formatting without structure can't fail")
+    }
+
+    /// Formats the packet in G-Earth's expression syntax, reading the body as described by `structure`
+    ///
+    /// The structure has one character per value: `i` (int), `s` (string), `b` (boolean), `B` (byte),
+    /// `u` (short), `l` (long) or `d` (double). Commas are ignored, bytes left after it are written as `{b:..}`.
+    pub fn to_expression_with_structure(&self, structure: &str) -> crate::error::Result<String> {
+        expression::format(self, structure)
+    }
+
     pub fn get_bytes_available(&mut self) -> usize {
         self.packet_in_bytes.len() - self.read_index
     }
@@ -119,10 +160,22 @@ impl HPacket {
         self.identifier == ""
     }
 
+    /// Copy of the whole packet, see [`bytes`](Self::bytes) to borrow them instead
     pub fn get_bytes(&self) -> Vec<u8> {
         self.packet_in_bytes.clone()
     }
 
+    /// The whole packet, including its length and header id
+    pub fn bytes(&self) -> &[u8] {
+        &self.packet_in_bytes
+    }
+
+    /// The bytes after the length and header id, reusing the packet's buffer
+    pub fn into_body(mut self) -> Vec<u8> {
+        self.packet_in_bytes.drain(..6.min(self.packet_in_bytes.len()));
+        self.packet_in_bytes
+    }
+
     pub fn reset_read_index(&mut self) {
         self.read_index = 6;
     }
@@ -139,7 +192,7 @@ impl HPacket {
         self.packet_in_bytes.len()
     }
 
-    pub fn header_id(&mut self) -> i16 {
+    pub fn header_id(&self) -> i16 {
         self.read_at::<i16>(4)
     }
 
@@ -147,25 +200,60 @@ impl HPacket {
         self.replace_internal::<i32>(0, self.bytes_length() as i32 - 4);
     }
 
+    /// Copy of the next `length` bytes, see [`read_slice`](Self::read_slice) to borrow them instead
     pub fn read_bytes(&mut self, length: usize) -> Vec<u8> {
+        self.read_slice(length).to_vec()
+    }
+
+    /// The next `length` bytes, moves the read index past them
+    pub fn read_slice(&mut self, length: usize) -> &[u8] {
         self.read_index += length;
-        self.packet_in_bytes[self.read_index-length..self.read_index].to_vec()
+        &self.packet_in_bytes[self.read_index-length..self.read_index]
     }
 
     pub fn read_bytes_at(&mut self, length: usize, index: usize) -> Vec<u8> {
         self.packet_in_bytes[index..index+length].to_vec()
     }
 
+    /// Cursor over the unread part of the packet, borrowing its bytes instead of copying them
+    pub fn reader(&self) -> PacketReader<'_> {
+        PacketReader::new(self.packet_in_bytes.get(self.read_index..).unwrap_or(&[]))
+    }
+
+    /// Reads a `T` at the read index and moves the read index past it
+    ///
+    /// Panics if the packet doesn't contain a valid `T`, see [`try_read`](Self::try_read)
     pub fn read<T: PacketVariable>(&mut self) -> T {
-        let bytes = self.packet_in_bytes[self.read_index..].to_vec();
-        let (res, size) = T::from_packet(bytes.clone());
+        let (res, size) = T::from_packet(&self.packet_in_bytes[self.read_index..]);
         self.read_index += size;
         res
     }
 
-    pub fn read_at<T: PacketVariable>(&mut self, index: usize) -> T {
-        let bytes = self.packet_in_bytes[index..].to_vec();
-        T::from_packet(bytes.clone()).0
+    /// Reads a `T` at the read index and moves the read index past it, leaves the read index untouched on failure
+    pub fn try_read<T: PacketVariable>(&mut self) -> Result<T, DecodeError> {
+        let (res, size) = self.try_read_sized_at(self.read_index)?;
+        self.read_index += size;
+        Ok(res)
+    }
+
+    /// Same as [`try_read`](Self::try_read), adds `field` to the path of the error
+    pub fn try_read_field<T: PacketVariable>(&mut self, field: impl Into<String>) -> Result<T, DecodeError> {
+        self.try_read().map_err(| e | e.in_field(field))
+    }
+
+    pub fn read_at<T: PacketVariable>(&self, index: usize) -> T {
+        T::from_packet(&self.packet_in_bytes[index..]).0
+    }
+
+    pub fn try_read_at<T: PacketVariable>(&self, index: usize) -> Result<T, DecodeError> {
+        self.try_read_sized_at(index).map(| (res, _) | res)
+    }
+
+    fn try_read_sized_at<T: PacketVariable>(&self, index: usize) -> Result<(T, usize), DecodeError> {
+        let body_offset = index.saturating_sub(6);
+        let bytes = self.packet_in_bytes.get(index..)
+            .ok_or_else(|| DecodeError::new(body_offset, "read past the end of the packet"))?;
+        T::try_from_packet(bytes).map_err(| e | e.shifted(body_offset))
     }
 
     pub fn append_bytes(&mut self, bytes: Vec<u8>) {
@@ -191,12 +279,8 @@ impl HPacket {
     }
 
     fn replace_internal<T: PacketVariable>(&mut self, index: usize, v: T) {
-        let old_bytes = self.packet_in_bytes[index..].to_vec();
-        let (_, old_size) = T::from_packet(old_bytes.clone());
-        let mut res = self.packet_in_bytes[..index].to_vec();
-        res.extend(v.to_packet());
-        res.extend(old_bytes[old_size..].to_vec());
-        self.packet_in_bytes = res.clone();
+        let (_, old_size) = T::from_packet(&self.packet_in_bytes[index..]);
+        self.packet_in_bytes.splice(index..index+old_size, v.to_packet());
     }
 
     pub fn replace<T: PacketVariable>(&mut self, index: usize, v: T) {
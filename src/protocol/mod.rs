@@ -1,4 +1,7 @@
 pub mod hdirection;
+pub mod dynamicpacket;
+mod expression;
 pub mod hpacket;
+pub mod packetreader;
 pub mod vars;
 pub mod hmessage;
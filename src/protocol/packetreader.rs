@@ -0,0 +1,48 @@
+use crate::error::DecodeError;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
+
+/// Cursor reading packet variables from a borrowed byte slice, without copying it
+#[derive(Clone, Copy, Debug)]
+pub struct PacketReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> PacketReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0
+        }
+    }
+
+    /// Amount of bytes read so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The bytes that haven't been read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    /// Reads a `T` and moves past it
+    ///
+    /// Panics if the bytes don't contain a valid `T`, see [`try_read`](Self::try_read)
+    pub fn read<T: PacketVariable>(&mut self) -> T {
+        expect_read(self.try_read())
+    }
+
+    /// Reads a `T` and moves past it, stays in place on failure
+    pub fn try_read<T: PacketVariable>(&mut self) -> Result<T, DecodeError> {
+        let (res, size) = T::try_from_packet(self.remaining())
+            .map_err(| e | e.shifted(self.position))?;
+        self.position = (self.position + size).min(self.bytes.len());
+        Ok(res)
+    }
+
+    /// Same as [`try_read`](Self::try_read), adds `field` to the path of the error
+    pub fn try_read_field<T: PacketVariable>(&mut self, field: impl Into<String>) -> Result<T, DecodeError> {
+        self.try_read().map_err(| e | e.in_field(field))
+    }
+}
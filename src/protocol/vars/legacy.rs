@@ -1,7 +1,8 @@
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::*;
 use crate::misc::hclient::{HClient, CUR_CLIENT};
-use super::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use super::packetvariable::{expect_read, PacketVariable};
 
 /**
  * Behaves like an i32 when connected to Flash or Nitro <br>
@@ -149,11 +150,15 @@ impl_legacy_bitwise! {
 }
 
 impl PacketVariable for LegacyId {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
         if *CUR_CLIENT.lock().unwrap() == HClient::Unity {
-            (Self(i64::from_packet(bytes).0), 8)
+            Ok((Self(i64::try_from_packet(bytes)?.0), 8))
         } else {
-            (Self(i32::from_packet(bytes).0 as i64), 4)
+            Ok((Self(i32::try_from_packet(bytes)?.0 as i64), 4))
         }
     }
 
@@ -167,11 +172,15 @@ impl PacketVariable for LegacyId {
 }
 
 impl PacketVariable for LegacyLength {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
         if *CUR_CLIENT.lock().unwrap() == HClient::Unity {
-            (Self(i16::from_packet(bytes).0 as i32), 8)
+            Ok((Self(i16::try_from_packet(bytes)?.0 as i32), 8))
         } else {
-            (Self(i32::from_packet(bytes).0), 4)
+            Ok((Self(i32::try_from_packet(bytes)?.0), 4))
         }
     }
 
@@ -185,12 +194,17 @@ impl PacketVariable for LegacyLength {
 }
 
 impl PacketVariable for LegacyStringId {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
         if *CUR_CLIENT.lock().unwrap() == HClient::Unity {
-            (Self(i64::from_packet(bytes).0), 8)
+            Ok((Self(i64::try_from_packet(bytes)?.0), 8))
         } else {
-            let (s, size) = String::from_packet(bytes);
-            (Self(s.parse::<i64>().unwrap()), size)
+            let (s, size) = String::try_from_packet(bytes)?;
+            let id = s.parse::<i64>().map_err(| _ | DecodeError::new(0, format!("invalid string id \"{s}\"")))?;
+            Ok((Self(id), size))
         }
     }
 
@@ -205,12 +219,17 @@ impl PacketVariable for LegacyStringId {
 }
 
 impl PacketVariable for LegacyDouble {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
         if *CUR_CLIENT.lock().unwrap() == HClient::Unity {
-            (Self(f64::from_packet(bytes).0), 8)
+            Ok((Self(f64::try_from_packet(bytes)?.0), 8))
         } else {
-            let (s, size) = String::from_packet(bytes);
-            (Self(s.parse::<f64>().unwrap()), size)
+            let (s, size) = String::try_from_packet(bytes)?;
+            let double = s.parse::<f64>().map_err(| _ | DecodeError::new(0, format!("invalid double \"{s}\"")))?;
+            Ok((Self(double), size))
         }
     }
 
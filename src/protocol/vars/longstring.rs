@@ -2,7 +2,8 @@ use std::fmt::{Debug, Display, Formatter};
 use std::ops::{Deref, DerefMut};
 use encoding::all::ISO_8859_1;
 use encoding::{DecoderTrap, EncoderTrap, Encoding};
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::vars::packetvariable::{ensure_bytes, expect_read, PacketVariable};
 
 pub struct LongString(pub String);
 
@@ -13,9 +14,16 @@ impl LongString {
 }
 
 impl PacketVariable for LongString {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let s_size = u32::from_packet(bytes.clone()).0 as usize;
-        (Self(ISO_8859_1.decode(&bytes[4..4+s_size], DecoderTrap::Ignore).expect("Couldn't read string")), 4+s_size)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let s_size = u32::try_from_packet(bytes)?.0 as usize;
+        ensure_bytes(bytes, 4+s_size)?;
+        let s = ISO_8859_1.decode(&bytes[4..4+s_size], DecoderTrap::Ignore)
+            .map_err(| e | DecodeError::new(4, format!("invalid string: {e}")))?;
+        Ok((Self(s), 4+s_size))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -2,17 +2,39 @@ use core::mem::size_of;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
-use crate::protocol::hpacket::HPacket;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
 use crate::protocol::vars::legacy::LegacyLength;
 
 pub trait PacketVariable {
-    /// Reads a variable from the beginning of the given bytes vector
+    /// Reads a variable from the beginning of the given bytes
     ///
     /// # Arguments
     ///
     ///
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized;
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized;
     fn to_packet(&self) -> Vec<u8>;
+
+    /// Fallible version of [`from_packet`](Self::from_packet), reports which field failed to read and where
+    ///
+    /// Defaults to calling `from_packet`, implementations that can fail should override it
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        Ok(Self::from_packet(bytes))
+    }
+}
+
+/// Unwraps the result of a `try_from_packet`, for the panicking `from_packet` implementations
+pub(crate) fn expect_read<T>(res: Result<T, DecodeError>) -> T {
+    res.unwrap_or_else(| e | panic!("Couldn't read packet variable {e}"))
+}
+
+/// Checks that at least `needed` bytes are left
+pub(crate) fn ensure_bytes(bytes: &[u8], needed: usize) -> Result<(), DecodeError> {
+    if bytes.len() < needed {
+        Err(DecodeError::new(0, format!("expected {needed} bytes, got {}", bytes.len())))
+    } else {
+        Ok(())
+    }
 }
 
 fn to_sized_array<T: Clone + Debug, const N: usize>(v: Vec<T>) -> [T; N] {
@@ -22,9 +44,14 @@ fn to_sized_array<T: Clone + Debug, const N: usize>(v: Vec<T>) -> [T; N] {
 macro_rules! impl_packet_variable {
     ($($ty:ident)+) => ($(
         impl PacketVariable for $ty {
-            fn from_packet(bytes: Vec<u8>) -> (Self, usize) {
-                let bytes_array: [u8; size_of::<$ty>()] = to_sized_array(bytes);
-                (Self::from_be_bytes(bytes_array), size_of::<$ty>())
+            fn from_packet(bytes: &[u8]) -> (Self, usize) {
+                expect_read(Self::try_from_packet(bytes))
+            }
+
+            fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+                ensure_bytes(bytes, size_of::<$ty>())?;
+                let bytes_array: [u8; size_of::<$ty>()] = bytes[..size_of::<$ty>()].try_into().unwrap();
+                Ok((Self::from_be_bytes(bytes_array), size_of::<$ty>()))
             }
 
             fn to_packet(&self) -> Vec<u8> {
@@ -37,8 +64,13 @@ macro_rules! impl_packet_variable {
 impl_packet_variable! { u8 i8 u16 i16 u32 i32 u64 i64 u128 i128 /*usize isize*/ f32 f64 }
 
 impl PacketVariable for bool {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) {
-        (bytes[0] != 0, 1)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        ensure_bytes(bytes, 1)?;
+        Ok((bytes[0] != 0, 1))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -47,10 +79,16 @@ impl PacketVariable for bool {
 }
 
 impl PacketVariable for String {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) {
-        let s_size = u16::from_packet(bytes.clone()).0 as usize;
-        let s = String::from_utf8(bytes[2..2+s_size].to_vec()).expect("Couldn't read string");
-        (s, 2+s_size)
+    fn from_packet(bytes: &[u8]) -> (Self, usize) {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        let s_size = u16::try_from_packet(bytes)?.0 as usize;
+        ensure_bytes(bytes, 2+s_size)?;
+        let s = String::from_utf8(bytes[2..2+s_size].to_vec())
+            .map_err(| e | DecodeError::new(2, format!("invalid string: {e}")))?;
+        Ok((s, 2+s_size))
     }
 
     fn to_packet(&self) -> Vec<u8> {
@@ -63,72 +101,84 @@ impl PacketVariable for String {
 }
 
 impl<T: PacketVariable + Clone> PacketVariable for Vec<T> {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
         let mut res: Vec<T> = Vec::new();
 
-        let len: LegacyLength = packet.read();
-        for _ in 0..*len {
-            res.push(packet.read());
+        let len: LegacyLength = reader.try_read_field("len")?;
+        for i in 0..*len {
+            res.push(reader.try_read().map_err(| e | e.in_field(format!("[{i}]")))?);
         }
 
-        (res, packet.read_index - 6)
+        Ok((res, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
-        let mut packet = HPacket::from_header_id(0);
-        packet.append(LegacyLength(self.len() as i32));
+        let mut res = LegacyLength(self.len() as i32).to_packet();
 
         for element in self.iter() {
-            packet.append(element.clone());
+            res.extend(element.to_packet());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        res
     }
 }
 
 impl<K: PacketVariable + Clone + Eq + Hash, V: PacketVariable + Clone> PacketVariable for HashMap<K, V> {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
         let mut res: HashMap<K, V> = HashMap::new();
 
-        let len: LegacyLength = packet.read();
-        for _ in 0..*len {
-            res.insert(packet.read(), packet.read());
+        let len: LegacyLength = reader.try_read_field("len")?;
+        for i in 0..*len {
+            let key = reader.try_read().map_err(| e | e.in_field(format!("[{i}].key")))?;
+            let value = reader.try_read().map_err(| e | e.in_field(format!("[{i}].value")))?;
+            res.insert(key, value);
         }
 
-        (res, packet.read_index - 6)
+        Ok((res, reader.position()))
     }
 
     fn to_packet(&self) -> Vec<u8> {
-        let mut packet = HPacket::from_header_id(0);
-        packet.append(LegacyLength(self.len() as i32));
+        let mut res = LegacyLength(self.len() as i32).to_packet();
 
         for (key, value) in self.iter() {
-            packet.append(key.clone());
-            packet.append(value.clone());
+            res.extend(key.to_packet());
+            res.extend(value.to_packet());
         }
 
-        packet.get_bytes()[6..].to_vec()
+        res
     }
 }
 
 macro_rules! impl_packet_tuple_variable {
     ($($size:expr, $($ty:ident:$n:tt),+);+;) => ($(
         impl<$($ty: PacketVariable + Clone),+> PacketVariable for ($($ty),+) {
-            fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-                let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-                (($(
-                    packet.read::<$ty>()
-                ),+), packet.read_index - 6)
+            fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+                expect_read(Self::try_from_packet(bytes))
+            }
+
+            fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+                let mut reader = PacketReader::new(bytes);
+                Ok((($(
+                    reader.try_read_field::<$ty>(stringify!($n))?
+                ),+), reader.position()))
             }
 
             fn to_packet(&self) -> Vec<u8> {
-                let mut packet = HPacket::from_header_id(0);
+                let mut res = Vec::new();
                 $(
-                    packet.append(self.$n.clone());
+                    res.extend(self.$n.to_packet());
                 )+
-                packet.get_bytes()[6..].to_vec()
+                res
             }
         }
     )+)
@@ -168,23 +218,27 @@ impl_packet_tuple_variable! {
 macro_rules! impl_packet_array_variable {
     ($($size:expr),+) => ($(
         impl<T: PacketVariable + Clone + Debug> PacketVariable for [T; $size] {
-            fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-                let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
+            fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+                expect_read(Self::try_from_packet(bytes))
+            }
+
+            fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+                let mut reader = PacketReader::new(bytes);
                 let mut res: Vec<T> = Vec::new();
-                for _ in 0..$size {
-                    res.push(packet.read());
+                for i in 0..$size {
+                    res.push(reader.try_read().map_err(| e | e.in_field(format!("[{i}]")))?);
                 }
-                (to_sized_array::<T, $size>(res), packet.read_index - 6)
+                Ok((to_sized_array::<T, $size>(res), reader.position()))
             }
 
             fn to_packet(&self) -> Vec<u8> {
-                let mut packet = HPacket::from_header_id(0);
+                let mut res = Vec::new();
 
                 for element in self.iter() {
-                    packet.append(element.clone());
+                    res.extend(element.to_packet());
                 }
 
-                packet.get_bytes()[6..].to_vec()
+                res
             }
         }
     )+)
@@ -193,8 +247,8 @@ macro_rules! impl_packet_array_variable {
 impl_packet_array_variable! { 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30 }
 
 impl<T: PacketVariable> PacketVariable for Option<T> {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        if bytes.len() > 0 {
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        if !bytes.is_empty() {
             let (res, size) = T::from_packet(bytes);
             (Some(res), size)
         } else {
@@ -202,6 +256,15 @@ impl<T: PacketVariable> PacketVariable for Option<T> {
         }
     }
 
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        if !bytes.is_empty() {
+            let (res, size) = T::try_from_packet(bytes)?;
+            Ok((Some(res), size))
+        } else {
+            Ok((None, 0))
+        }
+    }
+
     fn to_packet(&self) -> Vec<u8> {
         if self.is_some() {
             self.as_ref().unwrap().to_packet()
@@ -1,5 +1,4 @@
 use crate::protocol::hdirection::HDirection;
-use crate::protocol::hpacket::HPacket;
 use crate::protocol::vars::packetvariable::PacketVariable;
 
 #[derive(Clone, Debug, Default, PacketVariable, PartialEq, Eq)]
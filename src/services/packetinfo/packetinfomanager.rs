@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 use crate::protocol::hdirection::HDirection;
 use crate::protocol::hpacket::HPacket;
-use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::error::DecodeError;
+use crate::protocol::packetreader::PacketReader;
+use crate::protocol::vars::packetvariable::{expect_read, PacketVariable};
 use crate::services::packetinfo::packetinfo::PacketInfo;
 
 #[derive(Debug, Clone, Default)]
@@ -19,16 +21,35 @@ pub struct PacketInfoManager {
 }
 
 impl PacketVariable for PacketInfoManager {
-    fn from_packet(bytes: Vec<u8>) -> (Self, usize) where Self: Sized {
-        let mut packet = HPacket::from_header_id_and_bytes(0, bytes);
-        let size: i32 = packet.read();
+    fn from_packet(bytes: &[u8]) -> (Self, usize) where Self: Sized {
+        expect_read(Self::try_from_packet(bytes))
+    }
+
+    fn try_from_packet(bytes: &[u8]) -> Result<(Self, usize), DecodeError> where Self: Sized {
+        let mut reader = PacketReader::new(bytes);
+        let size: i32 = reader.try_read_field("size")?;
 
         let mut list: Vec<PacketInfo> = Vec::new();
         for _ in 0..size {
-            list.push(packet.read());
+            list.push(reader.try_read()?);
         }
 
-        (PacketInfoManager {
+        Ok((PacketInfoManager::new(list), reader.position()))
+    }
+
+    fn to_packet(&self) -> Vec<u8> {
+        let mut packet = HPacket::from_header_id(0);
+        packet.append(self.packet_info_list.len() as i32);
+        for packet_info in self.packet_info_list.iter() {
+            packet.append(packet_info.clone());
+        }
+        packet.into_body()
+    }
+}
+
+impl PacketInfoManager {
+    pub fn new(list: Vec<PacketInfo>) -> Self {
+        PacketInfoManager {
             header_id_to_message_incoming: list.iter()
                 .filter(| i | i.destination == HDirection::ToClient)
                 .map(| i | (i.header_id, i.clone()))
@@ -75,20 +96,9 @@ impl PacketVariable for PacketInfoManager {
                 }),
 
             packet_info_list: list
-        }, packet.read_index - 6)
-    }
-
-    fn to_packet(&self) -> Vec<u8> {
-        let mut packet = HPacket::from_header_id(0);
-        packet.append(self.packet_info_list.len() as i32);
-        for packet_info in self.packet_info_list.iter() {
-            packet.append(packet_info.clone());
         }
-        packet.get_bytes()[6..].to_vec()
     }
-}
 
-impl PacketInfoManager {
     pub fn get_all_packet_info_from_header_id(&mut self, direction: HDirection, header_id: i32) -> Vec<PacketInfo> {
         if direction == HDirection::ToClient {
             self.header_id_to_message_incoming.entry(header_id).or_default().clone()
@@ -0,0 +1,377 @@
+//! A fake G-Earth to test extensions without a running client
+//!
+//! Needs the `testing` feature, e.g. `g-rust = { version = "..", features = ["testing"] }` in `[dev-dependencies]`
+
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use crate::error::{Error, Result};
+use crate::extension::extension::ExtensionInfo;
+use crate::misc::connectioninfo::ConnectionInfo;
+use crate::misc::hostinfo::HostInfo;
+use crate::misc::transport::ChannelTransport;
+use crate::misc::messages::{IncomingMessageIds, OutgoingMessageIds};
+use crate::protocol::hdirection::HDirection;
+use crate::protocol::hmessage::HMessage;
+use crate::protocol::hpacket::HPacket;
+use crate::protocol::vars::longstring::LongString;
+use crate::protocol::vars::packetvariable::PacketVariable;
+use crate::services::packetinfo::packetinfomanager::PacketInfoManager;
+
+/// How long the mock waits for a message from the extension by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Local server speaking G-Earth's extension protocol
+///
+/// Run the extension on another thread with [`args`](Self::args) as its arguments, [`accept`](Self::accept) it
+/// and drive it with the G-Earth messages below. Everything the extension sends is recorded.
+/// Dropping the mock disconnects the extension, which makes its `run` return `Ok`.
+/// Use [`channel`](Self::channel) and [`Extension::run_with_transport`](crate::extension::extension::Extension::run_with_transport)
+/// to test without sockets.
+///
+/// ```no_run
+/// # use g_rust::extension::extension::Extension;
+/// # use g_rust::testing::MockGEarth;
+/// let mut g_earth = MockGEarth::bind().unwrap();
+/// let mut ext: Extension<()> = Extension::new();
+/// ext.args = g_earth.args();
+/// let ext_thread = std::thread::spawn(move || ext.run());
+///
+/// g_earth.accept().unwrap();
+/// g_earth.init(false, Default::default()).unwrap();
+/// drop(g_earth);
+/// ext_thread.join().unwrap().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MockGEarth {
+    listener: Option<TcpListener>,
+    stream: Option<MockStream>,
+    timeout: Duration,
+
+    /// Flags sent back when the extension requests them
+    pub flags: Vec<String>,
+
+    received: Vec<HPacket>,
+    manipulated_packets: Vec<HMessage>,
+    sent_messages: Vec<(HDirection, HPacket)>,
+    console_logs: Vec<String>,
+    conversion_requests: Vec<HPacket>,
+    answered_conversions: usize
+}
+
+impl MockGEarth {
+    /// Binds to a free local port
+    pub fn bind() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(Error::Connection)?;
+        Ok(Self::new(Some(listener), None))
+    }
+
+    /// Creates a mock connected to the returned transport, to be passed to
+    /// [`Extension::run_with_transport`](crate::extension::extension::Extension::run_with_transport)
+    pub fn channel() -> (Self, ChannelTransport) {
+        let (g_earth, extension) = ChannelTransport::pair();
+        (Self::new(None, Some(MockStream::Channel(g_earth))), extension)
+    }
+
+    fn new(listener: Option<TcpListener>, stream: Option<MockStream>) -> Self {
+        MockGEarth {
+            listener,
+            stream,
+            timeout: DEFAULT_TIMEOUT,
+
+            flags: Vec::new(),
+
+            received: Vec::new(),
+            manipulated_packets: Vec::new(),
+            sent_messages: Vec::new(),
+            console_logs: Vec::new(),
+            conversion_requests: Vec::new(),
+            answered_conversions: 0
+        }
+    }
+
+    /// Port the mock listens on, 0 for a [`channel`](Self::channel) mock
+    pub fn port(&self) -> u16 {
+        self.listener.as_ref()
+            .and_then(| listener | listener.local_addr().ok())
+            .map(| addr | addr.port())
+            .unwrap_or(0)
+    }
+
+    /// Arguments making an extension connect to this mock
+    pub fn args(&self) -> Vec<String> {
+        vec![String::from("-p"), self.port().to_string()]
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Waits for the extension to connect, a [`channel`](Self::channel) mock is connected already
+    pub fn accept(&mut self) -> Result<()> {
+        let Some(listener) = &self.listener else {
+            return Ok(());
+        };
+        let (stream, _) = listener.accept().map_err(Error::Connection)?;
+        stream.set_nodelay(true).map_err(Error::Connection)?;
+        self.stream = Some(MockStream::Tcp(stream));
+        Ok(())
+    }
+
+    /// Closes the connection with the extension
+    pub fn disconnect(&mut self) {
+        self.stream = None;
+    }
+
+    /// Every message received from the extension, in order
+    pub fn received(&self) -> &[HPacket] {
+        &self.received
+    }
+
+    /// Messages the extension returned after intercepting them
+    pub fn manipulated_packets(&self) -> &[HMessage] {
+        &self.manipulated_packets
+    }
+
+    /// Packets the extension sent to the client or server
+    pub fn sent_messages(&self) -> &[(HDirection, HPacket)] {
+        &self.sent_messages
+    }
+
+    /// Lines the extension wrote to the console, including the colour prefix, e.g. `[green] ...`
+    pub fn console_logs(&self) -> &[String] {
+        &self.console_logs
+    }
+
+    /// `PACKET_TO_STRING_REQUEST`s and `STRING_TO_PACKET_REQUEST`s of the extension, in order
+    pub fn conversion_requests(&self) -> &[HPacket] {
+        &self.conversion_requests
+    }
+
+    /// Sends `INFO_REQUEST` and returns the info the extension answers with
+    pub fn info_request(&mut self) -> Result<ExtensionInfo> {
+        self.write(HPacket::from_header_id(IncomingMessageIds::INFO_REQUEST))?;
+        let mut response = self.receive_until(OutgoingMessageIds::EXTENSION_INFO)?;
+        let (name, author, version, description) = response.try_read()?;
+        Ok(ExtensionInfo {
+            name,
+            description,
+            author,
+            version
+        })
+    }
+
+    /// Sends `INIT` and waits for the extension to report it's initialized
+    pub fn init(&mut self, delayed_init: bool, host_info: HostInfo) -> Result<()> {
+        let mut packet = HPacket::from_header_id(IncomingMessageIds::INIT);
+        packet.append((delayed_init, host_info));
+        self.write(packet)?;
+        self.receive_until(OutgoingMessageIds::EXTENSION_CONSOLE_LOG).map(| _ | ())
+    }
+
+    /// Sends `packet` to the extension as is, e.g. to test how it handles malformed messages
+    pub fn send_raw(&mut self, packet: HPacket) -> Result<()> {
+        self.write(packet)
+    }
+
+    pub fn connection_start(&mut self, connection_info: ConnectionInfo, packet_info_manager: PacketInfoManager) -> Result<()> {
+        let mut packet = HPacket::from_header_id(IncomingMessageIds::CONNECTION_START);
+        packet.append((connection_info, packet_info_manager));
+        self.write(packet)
+    }
+
+    pub fn connection_end(&mut self) -> Result<()> {
+        self.write(HPacket::from_header_id(IncomingMessageIds::CONNECTION_END))
+    }
+
+    pub fn double_click(&mut self) -> Result<()> {
+        self.write(HPacket::from_header_id(IncomingMessageIds::ON_DOUBLE_CLICK))
+    }
+
+    pub fn update_host_info(&mut self, host_info: HostInfo) -> Result<()> {
+        let mut packet = HPacket::from_header_id(IncomingMessageIds::UPDATE_HOST_INFO);
+        packet.append(host_info);
+        self.write(packet)
+    }
+
+    /// Lets the extension intercept `msg` and returns it as manipulated by the extension
+    pub fn intercept(&mut self, mut msg: HMessage) -> Result<HMessage> {
+        let mut packet = HPacket::from_header_id(IncomingMessageIds::PACKET_INTERCEPT);
+        packet.append(LongString(msg.stringify()));
+        self.write(packet)?;
+        self.receive_until(OutgoingMessageIds::MANIPULATED_PACKET)?;
+        Ok(self.manipulated_packets.last().unwrap().clone())
+    }
+
+    /// Waits until the extension sent at least `count` packets to the client or server in total
+    pub fn wait_for_sent_messages(&mut self, count: usize) -> Result<&[(HDirection, HPacket)]> {
+        while self.sent_messages.len() < count {
+            self.receive()?;
+        }
+        Ok(&self.sent_messages)
+    }
+
+    /// Waits until the extension wrote at least `count` lines to the console in total
+    pub fn wait_for_console_logs(&mut self, count: usize) -> Result<&[String]> {
+        while self.console_logs.len() < count {
+            self.receive()?;
+        }
+        Ok(&self.console_logs)
+    }
+
+    /// Waits until the extension requested at least `count` conversions in total and answers the unanswered ones in order
+    ///
+    /// Packets are converted to G-Earth's notation and [expressions](HPacket::to_expression),
+    /// strings are read as [expressions](HPacket::from_expression)
+    pub fn answer_conversions(&mut self, count: usize) -> Result<()> {
+        while self.conversion_requests.len() < count {
+            self.receive()?;
+        }
+        while self.answered_conversions < self.conversion_requests.len() {
+            let mut request = self.conversion_requests[self.answered_conversions].clone();
+            self.answered_conversions += 1;
+            let response = match request.header_id() {
+                OutgoingMessageIds::PACKET_TO_STRING_REQUEST => {
+                    let packet = HPacket::from_string(request.try_read::<LongString>()?.0);
+                    let expression = packet.to_expression();
+                    let mut response = HPacket::from_header_id(IncomingMessageIds::PACKET_TO_STRING_RESPONSE);
+                    response.append(LongString(g_earth_string(&packet)));
+                    response.append(expression.len() as u32);
+                    response.append_bytes(expression.into_bytes());
+                    response
+                },
+                _ => {
+                    let length: u32 = request.try_read()?;
+                    let s = String::from_utf8_lossy(request.read_slice(length as usize)).to_string();
+                    let stringified = HPacket::from_expression(&s).map(| packet | packet.stringify()).unwrap_or_default();
+                    let mut response = HPacket::from_header_id(IncomingMessageIds::STRING_TO_PACKET_RESPONSE);
+                    response.append(LongString(stringified));
+                    response
+                }
+            };
+            self.write(response)?;
+        }
+        Ok(())
+    }
+
+    /// Receives the next message from the extension and records it
+    ///
+    /// Flag requests are answered with [`flags`](Self::flags)
+    pub fn receive(&mut self) -> Result<HPacket> {
+        let mut packet = self.read_packet()?;
+        self.received.push(packet.clone());
+
+        match packet.header_id() {
+            OutgoingMessageIds::MANIPULATED_PACKET => {
+                let message: LongString = packet.try_read()?;
+                self.manipulated_packets.push(HMessage::from_string(message.0));
+            },
+            OutgoingMessageIds::SEND_MESSAGE => {
+                let (to_server, length): (bool, i32) = packet.try_read()?;
+                let direction = if to_server { HDirection::ToServer } else { HDirection::ToClient };
+                self.sent_messages.push((direction, HPacket::from_bytes(packet.read_slice(length as usize).to_vec())));
+            },
+            OutgoingMessageIds::EXTENSION_CONSOLE_LOG => self.console_logs.push(packet.try_read()?),
+            OutgoingMessageIds::PACKET_TO_STRING_REQUEST | OutgoingMessageIds::STRING_TO_PACKET_REQUEST => self.conversion_requests.push(packet.clone()),
+            OutgoingMessageIds::REQUEST_FLAGS => {
+                let mut response = HPacket::from_header_id(IncomingMessageIds::FLAGS_CHECK);
+                response.append(self.flags.len() as i32);
+                for flag in self.flags.clone() {
+                    response.append(flag);
+                }
+                self.write(response)?;
+            },
+            _ => {}
+        }
+
+        packet.reset_read_index();
+        Ok(packet)
+    }
+
+    fn receive_until(&mut self, header_id: i16) -> Result<HPacket> {
+        loop {
+            let packet = self.receive()?;
+            if packet.header_id() == header_id {
+                return Ok(packet);
+            }
+        }
+    }
+
+    fn stream(&mut self) -> Result<&mut MockStream> {
+        self.stream.as_mut().ok_or(Error::Disconnected)
+    }
+
+    fn write(&mut self, packet: HPacket) -> Result<()> {
+        self.stream()?.write_all(packet.bytes()).map_err(Error::Connection)
+    }
+
+    fn read(&mut self, length: usize) -> Result<Vec<u8>> {
+        let timeout = self.timeout;
+        let stream = self.stream()?;
+        stream.set_read_timeout(timeout).map_err(Error::Connection)?;
+        let mut bytes = vec![0u8; length];
+        stream.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_packet(&mut self) -> Result<HPacket> {
+        let mut bytes = self.read(4)?;
+        let length = i32::from_packet(&bytes).0;
+        if length < 2 {
+            return Err(Error::Framing(length));
+        }
+        bytes.append(&mut self.read(length as usize)?);
+        Ok(HPacket::from_bytes(bytes))
+    }
+}
+
+/// The packet's bytes as G-Earth shows them, with bytes that aren't printable written as `[n]`
+fn g_earth_string(packet: &HPacket) -> String {
+    packet.bytes().iter()
+        .map(| &byte | if (32..127).contains(&byte) { (byte as char).to_string() } else { format!("[{byte}]") })
+        .collect()
+}
+
+#[derive(Debug)]
+enum MockStream {
+    Tcp(TcpStream),
+    Channel(ChannelTransport)
+}
+
+impl MockStream {
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        match self {
+            MockStream::Tcp(stream) => stream.set_read_timeout(Some(timeout)),
+            MockStream::Channel(transport) => {
+                transport.set_read_timeout(Some(timeout));
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Read for MockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            MockStream::Tcp(stream) => stream.read(buf),
+            MockStream::Channel(transport) => transport.read(buf)
+        }
+    }
+}
+
+impl Write for MockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            MockStream::Tcp(stream) => stream.write(buf),
+            MockStream::Channel(transport) => transport.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            MockStream::Tcp(stream) => stream.flush(),
+            MockStream::Channel(transport) => transport.flush()
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use g_rust::extension::arguments::{Argument, Arguments, DEFAULT_PORT};
+use g_rust::Error;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(| arg | arg.to_string()).collect()
+}
+
+#[test]
+fn g_earth_arguments() {
+    let arguments = Arguments::parse(&[], &[]).unwrap();
+    assert_eq!(DEFAULT_PORT, arguments.port().unwrap());
+    assert_eq!(None, arguments.file());
+
+    let arguments = Arguments::parse(&args(&["extension.exe", "-P", "9093", "--filename=ext.jar", "-c", "abc"]), &[]).unwrap();
+    assert_eq!(9093, arguments.port().unwrap());
+    assert_eq!(Some("ext.jar"), arguments.file());
+    assert_eq!(Some("abc"), arguments.cookie());
+}
+
+#[test]
+fn invalid_arguments() {
+    assert!(matches!(Arguments::parse(&args(&["-p"]), &[]), Err(Error::Arguments(_))));
+    assert!(matches!(Arguments::parse(&args(&["--port", "abc"]), &[]), Err(Error::Arguments(_))));
+    assert!(matches!(Arguments::parse(&args(&["--port", "70000"]), &[]), Err(Error::Arguments(_))));
+}
+
+#[test]
+fn custom_arguments() {
+    let declared = vec![
+        Argument::new("--room").alias("-r").env("G_RUST_TEST_ROOM").required(),
+        Argument::new("--verbose")
+    ];
+
+    let arguments = Arguments::parse_with_env(&args(&["-r=-5", "--unknown", "x"]), &declared, | _ | None).unwrap();
+    assert_eq!(Some(-5), arguments.get_parsed::<i32>("--room").unwrap());
+    assert_eq!(None, arguments.get_parsed::<bool>("--verbose").unwrap());
+    assert!(arguments.get_parsed::<u32>("--room").is_err());
+
+    assert!(matches!(Arguments::parse_with_env(&[], &declared, | _ | None), Err(Error::Arguments(_))));
+    let env = | var: &str | (var == "G_RUST_TEST_ROOM").then(|| String::from("12"));
+    assert_eq!(Some(12), Arguments::parse_with_env(&[], &declared, env).unwrap().get_parsed::<i32>("--room").unwrap());
+}
+
+#[test]
+fn flags() {
+    let declared = vec![
+        Argument::new("--room").alias("-r"),
+        Argument::new("--verbose").alias("-v"),
+        Argument::new("--dry-run")
+    ];
+
+    let arguments = Arguments::parse_with_env(&args(&["-v", "-r", "5", "--dry-run"]), &declared, | _ | None).unwrap();
+    assert_eq!(Some(true), arguments.get_parsed::<bool>("--verbose").unwrap());
+    assert_eq!(Some(5), arguments.get_parsed::<i32>("--room").unwrap());
+    assert_eq!(Some(true), arguments.get_parsed::<bool>("--dry-run").unwrap());
+
+    let arguments = Arguments::parse_with_env(&args(&["--verbose=false", "-r"]), &declared, | _ | None).unwrap();
+    assert_eq!(Some(false), arguments.get_parsed::<bool>("--verbose").unwrap());
+    assert_eq!(Some("true"), arguments.get("--room"));
+    assert_eq!(None, arguments.get("--dry-run"));
+}
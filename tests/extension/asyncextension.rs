@@ -0,0 +1,99 @@
+#![cfg(feature = "tokio")]
+
+use std::thread;
+use std::time::Duration;
+use g_rust::extension::arguments::Argument;
+use g_rust::extension::asyncextension::{AsyncExtension, AsyncExtensionHandle};
+use g_rust::extension::extension::PortSource;
+use g_rust::extension::parsers::{incoming, outgoing};
+use g_rust::extension::reconnect::ReconnectPolicy;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::testing::MockGEarth;
+use g_rust::Error;
+use super::extension::{chat, connection_info, packet_info_manager};
+
+#[derive(Debug, Default)]
+struct Test {
+    chats: usize
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[test]
+fn test_async_listeners() {
+    let mut ext: AsyncExtension<Test> = AsyncExtension::new();
+    ext.on_click(| handle: AsyncExtensionHandle<Test> | async move {
+        let chats = handle.globals().lock().await.chats;
+        handle.send(chat(&format!("G-Rust has seen {chats} chats"))).await.unwrap();
+    });
+    ext.intercept(| handle: AsyncExtensionHandle<Test>, msg: HMessage, chat: outgoing::Chat | async move {
+        handle.globals().lock().await.chats += 1;
+        (msg, chat)
+    });
+
+    assert_send(&ext.run());
+}
+
+#[tokio::test]
+async fn test_async_intercept() {
+    let mut g_earth = MockGEarth::bind().unwrap();
+    let mut ext: AsyncExtension<Test> = AsyncExtension::new();
+    ext.args = g_earth.args();
+    ext.info.name = String::from("G-Rust async test");
+    ext.capabilities.can_delete = false;
+    ext.intercept(| handle: AsyncExtensionHandle<Test>, mut msg: HMessage, chat: outgoing::Chat | async move {
+        handle.globals().lock().await.chats += 1;
+        handle.send(super::extension::chat(&format!("G-Rust heard {}", chat.text))).await.unwrap();
+        msg.blocked = true;
+        (msg, chat)
+    });
+
+    let g_earth_thread = thread::spawn(move || {
+        g_earth.accept().unwrap();
+        assert_eq!("G-Rust async test", g_earth.info_request().unwrap().name);
+        let mut info = g_earth.received().last().unwrap().clone();
+        let (_, _, _, _, clickable, _, _, _, can_leave, can_delete): (String, String, String, String, bool, bool, String, String, bool, bool) = info.read();
+        assert_eq!((false, true, false), (clickable, can_leave, can_delete));
+
+        g_earth.init(false, HostInfo::default()).unwrap();
+        g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+        let mut packet = HPacket::from_header_id(1);
+        packet.append((String::from("hello"), 0, 0));
+        let msg = g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToServer, 0)).unwrap();
+        let (direction, mut reply) = g_earth.wait_for_sent_messages(1).unwrap()[0].clone();
+        (msg.blocked, direction, reply.read::<incoming::Chat>())
+    });
+
+    ext.run().await.unwrap();
+    let (blocked, direction, reply) = g_earth_thread.join().unwrap();
+    assert!(blocked);
+    assert_eq!(HDirection::ToClient, direction);
+    assert_eq!(chat("G-Rust heard hello"), reply);
+    assert_eq!(1, ext.globals().lock().await.chats);
+}
+
+#[tokio::test]
+async fn test_async_reconnect_config_error() {
+    let mut ext: AsyncExtension<Test> = AsyncExtension::new();
+    ext.port_source = PortSource::Env(String::from("G_RUST_TEST_UNSET_PORT"));
+    ext.reconnect_policy = Some(ReconnectPolicy {
+        initial_delay: Duration::from_millis(10),
+        ..ReconnectPolicy::default()
+    });
+    assert!(matches!(ext.run().await, Err(Error::Arguments(_))));
+}
+
+#[tokio::test]
+async fn test_async_arguments() {
+    let mut ext = AsyncExtension::with_globals(Test { chats: 3 });
+    ext.args = vec![String::from("--room"), String::from("5")];
+    ext.add_argument(Argument::new("--room").required());
+    assert_eq!(Some(5), ext.arguments().unwrap().get_parsed::<i32>("--room").unwrap());
+    assert_eq!(3, ext.globals().lock().await.chats);
+
+    ext.args = Vec::new();
+    assert!(matches!(ext.run().await, Err(Error::Arguments(_))));
+}
@@ -0,0 +1,74 @@
+use std::thread;
+use g_rust::extension::extension::{Extension, PortSource};
+use g_rust::extension_info;
+use g_rust::testing::MockGEarth;
+
+/// Header id of the `EXTENSION_INFO` packet the extension answers an info request with
+const EXTENSION_INFO: i16 = 1;
+
+#[derive(Debug)]
+struct Globals {
+    prefix: String
+}
+
+fn capabilities(g_earth: &MockGEarth) -> (bool, bool, bool) {
+    let mut info = g_earth.received().iter()
+        .find(| packet | packet.header_id() == EXTENSION_INFO)
+        .unwrap()
+        .clone();
+    let (_, _, _, _, clickable, _, _, _, can_leave, can_delete): (String, String, String, String, bool, bool, String, String, bool, bool) = info.read();
+    (clickable, can_leave, can_delete)
+}
+
+#[test]
+fn test_builder() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext = Extension::builder()
+        .info(extension_info!())
+        .name("G-Rust builder test")
+        .can_leave(false)
+        .can_delete(false)
+        .clickable(true)
+        .globals(Globals { prefix: String::from(":") })
+        .build();
+    assert_eq!(env!("CARGO_PKG_VERSION"), ext.info.version);
+    assert_eq!(":", ext.globals.prefix);
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    let info = g_earth.info_request().unwrap();
+    assert_eq!("G-Rust builder test", info.name);
+    assert_eq!(env!("CARGO_PKG_AUTHORS"), info.author);
+    assert_eq!((true, false, false), capabilities(&g_earth));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_default_capabilities() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<()> = Extension::new();
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.info_request().unwrap();
+    assert_eq!((false, true, true), capabilities(&g_earth));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_port_source() {
+    let mut g_earth = MockGEarth::bind().unwrap();
+    let mut ext = Extension::builder()
+        .args(Vec::new())
+        .port(PortSource::Fixed(g_earth.port()))
+        .build();
+    let ext_thread = thread::spawn(move || ext.run());
+
+    g_earth.accept().unwrap();
+    g_earth.info_request().unwrap();
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
@@ -0,0 +1,76 @@
+use std::thread;
+use g_rust::extension::capture::{CaptureRecord, Recorder, Replayer};
+use g_rust::extension::extension::Extension;
+use g_rust::extension::parsers::{incoming, outgoing};
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::protocol::vars::packetvariable::PacketVariable;
+use g_rust::testing::MockGEarth;
+use g_rust::Error;
+use super::extension::{chat, connection_info, packet_info_manager};
+
+fn extension() -> Extension<()> {
+    let mut ext: Extension<()> = Extension::new();
+    ext.intercept(| _ext: &mut Extension<()>, _msg: &mut HMessage, chat: &mut incoming::Chat | chat.text = chat.text.to_uppercase());
+    ext.intercept(| _ext: &mut Extension<()>, msg: &mut HMessage, _chat: &mut outgoing::Chat | msg.blocked = true);
+    ext
+}
+
+fn message(header_id: i16, direction: HDirection, index: i32, body: impl PacketVariable) -> HMessage {
+    let mut packet = HPacket::from_header_id(header_id);
+    packet.append(body);
+    HMessage::from_packet_dir_index(packet, direction, index)
+}
+
+#[test]
+fn test_record_and_replay() {
+    let path = std::env::temp_dir().join(format!("g-rust-capture-{}.grcap", std::process::id()));
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext = extension();
+    ext.start_recording(Recorder::create(&path).unwrap()).unwrap();
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    let intercepted = vec![
+        g_earth.intercept(message(1, HDirection::ToClient, 0, chat("hello"))).unwrap(),
+        g_earth.intercept(message(1, HDirection::ToServer, 1, (String::from("hi"), 0, 0))).unwrap(),
+        g_earth.intercept(message(99, HDirection::ToClient, 2, 5)).unwrap()
+    ];
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+
+    let records: Vec<CaptureRecord> = Replayer::open(&path).unwrap().map(| record | record.unwrap()).collect();
+    assert_eq!(4, records.len());
+    assert!(matches!(&records[0], CaptureRecord::PacketInfo(_)));
+    let captured: Vec<_> = records[1..].iter().map(| record | match record {
+        CaptureRecord::Message(captured) => (captured.index, captured.header_id, captured.name.clone(), captured.blocked, captured.modified),
+        record => panic!("unexpected record {record:?}")
+    }).collect();
+    assert_eq!(vec![
+        (0, 1, Some(String::from("Chat")), false, true),
+        (1, 1, Some(String::from("Chat")), true, false),
+        (2, 99, None, false, false)
+    ], captured);
+
+    let mut ext = extension();
+    let replayed = Replayer::open(&path).unwrap().replay(&mut ext).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(replayed.iter().all(| replayed | replayed.matches_capture()));
+    let outcome = | mut msg: HMessage | (msg.get_packet().get_bytes(), msg.blocked);
+    assert_eq!(
+        intercepted.into_iter().map(outcome).collect::<Vec<_>>(),
+        replayed.into_iter().map(| replayed | outcome(replayed.message)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_invalid_capture() {
+    assert!(matches!(Replayer::new(&b"GRCAP"[..]), Err(Error::Capture(_))));
+    assert!(matches!(Replayer::new(&b"G-Earth"[..]), Err(Error::Capture(_))));
+
+    let mut replayer = Replayer::new(&b"GRCAP\x01\x00\x00\x00\x06\x00\x07"[..]).unwrap();
+    assert!(matches!(replayer.next_record(), Err(Error::Capture(_))));
+}
@@ -0,0 +1,179 @@
+use std::sync::mpsc;
+use std::thread;
+use g_rust::extension::command::CommandArgs;
+use g_rust::extension::extension::Extension;
+use g_rust::extension::parsers::{incoming, outgoing};
+use g_rust::extension::parsers::baseparser::BaseParser;
+use g_rust::extension::parsers::subparsers::User;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::protocol::vars::legacy::LegacyId;
+use g_rust::protocol::vars::packetvariable::PacketVariable;
+use g_rust::services::packetinfo::packetinfomanager::PacketInfoManager;
+use g_rust::testing::MockGEarth;
+use super::extension::{connection_info, packet_info};
+
+fn packet_info_manager() -> PacketInfoManager {
+    PacketInfoManager::new(vec![
+        packet_info(1, "Chat", HDirection::ToServer),
+        packet_info(2, "Shout", HDirection::ToServer),
+        packet_info(3, "Whisper", HDirection::ToServer),
+        packet_info(4, "Whisper", HDirection::ToClient),
+        packet_info(5, "UserObject", HDirection::ToClient),
+        packet_info(6, "Users", HDirection::ToClient)
+    ])
+}
+
+/// Runs `ext` against a mock G-Earth that has started a connection
+fn start(mut ext: Extension<()>) -> (MockGEarth, thread::JoinHandle<g_rust::Result<()>>) {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    ext.info.name = String::from("Commands");
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    (g_earth, ext_thread)
+}
+
+/// Lets the extension intercept `object` as a packet with `header_id`, returns whether it was blocked
+fn intercept<B: BaseParser + PacketVariable>(g_earth: &mut MockGEarth, header_id: i16, object: B) -> bool {
+    let mut packet = HPacket::from_header_id(header_id);
+    packet.append(object);
+    g_earth.intercept(HMessage::from_packet_dir_index(packet, B::get_direction(), 0)).unwrap().blocked
+}
+
+fn chat(text: &str) -> outgoing::Chat {
+    outgoing::Chat {
+        text: text.to_string(),
+        ..outgoing::Chat::default()
+    }
+}
+
+fn player(id: i64, room_index: i32) -> User {
+    User::Player {
+        id: LegacyId(id),
+        name: String::from("G-Rust"),
+        custom: String::new(),
+        figure: String::new(),
+        room_index,
+        x: 0,
+        y: 0,
+        z: Default::default(),
+        dir: 0,
+        sex: String::from("M"),
+        group_id: LegacyId(0),
+        group_status: 0,
+        group_name: String::new(),
+        swim_figure: String::new(),
+        achievement_score: 0,
+        is_moderator: false
+    }
+}
+
+#[test]
+fn parse_args() {
+    assert_eq!(Ok(()), <()>::parse(&[]));
+    assert!(<()>::parse(&["x"]).is_err());
+
+    assert_eq!(Ok((5, String::from("abc"))), <(i32, String)>::parse(&["5", "abc"]));
+    assert!(<(i32, String)>::parse(&["5"]).is_err());
+    assert_eq!(Err("\"x\" is not a valid i32".to_string()), <(i32,)>::parse(&["x"]));
+
+    assert_eq!(Ok(vec![1, 2, 3]), Vec::<u8>::parse(&["1", "2", "3"]));
+    assert_eq!(Ok(String::from("hello world")), String::parse(&["hello", "world"]));
+    assert!(String::parse(&[]).is_err());
+}
+
+#[test]
+fn usage() {
+    assert_eq!("", <()>::usage());
+    assert_eq!("<i32> <String>", <(i32, String)>::usage());
+    assert_eq!("[<u8>...]", Vec::<u8>::usage());
+    assert_eq!("<message>", String::usage());
+}
+
+#[test]
+fn dispatch_and_block() {
+    let mut ext: Extension<()> = Extension::new();
+    let (sender, calls) = mpsc::channel();
+    ext.command(":add", move | _ext, (a, b): (i32, i32) | sender.send(a + b).unwrap());
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert!(intercept(&mut g_earth, 1, chat(":add 1 2")));
+    assert!(intercept(&mut g_earth, 2, outgoing::Shout { text: String::from(":ADD 3 4"), chat_style: 0 }));
+    assert!(intercept(&mut g_earth, 3, outgoing::Whisper { text: String::from("friend :add 5 6"), chat_style: 0 }));
+    assert!(!intercept(&mut g_earth, 1, chat("hello :add 7 8")));
+    assert!(!intercept(&mut g_earth, 1, chat(":subtract 1 2")));
+    assert_eq!(vec![3, 7, 11], calls.try_iter().collect::<Vec<_>>());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn alias() {
+    let mut ext: Extension<()> = Extension::new();
+    let (sender, calls) = mpsc::channel();
+    ext.command(":say", move | _ext, text: String | sender.send(text).unwrap())
+        .alias(":s");
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert!(intercept(&mut g_earth, 1, chat(":s hello world")));
+    assert!(intercept(&mut g_earth, 1, chat(":say hi")));
+    assert_eq!(vec!["hello world", "hi"], calls.try_iter().collect::<Vec<_>>());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn help() {
+    let mut ext: Extension<()> = Extension::new();
+    ext.command(":add", | _ext, _args: (i32, i32) | {})
+        .description("Adds two numbers");
+    ext.command(":say", | _ext, _text: String | {})
+        .alias(":s");
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let logs = g_earth.console_logs().len();
+    // Without a room index the listing goes to the console
+    assert!(intercept(&mut g_earth, 1, chat(":help")));
+    let help: Vec<String> = g_earth.wait_for_console_logs(logs + 3).unwrap()[logs..].to_vec();
+    assert_eq!(vec![
+        "[white] [Commands] :help - Lists all commands",
+        "[white] [Commands] :add <i32> <i32> - Adds two numbers",
+        "[white] [Commands] :say, :s <message>"
+    ], help);
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn feedback_whisper() {
+    let mut ext: Extension<()> = Extension::new();
+    ext.command(":add", | _ext, _args: (i32, i32) | {});
+    ext.command(":ping", | ext, () | ext.feedback("pong").unwrap());
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert!(!intercept(&mut g_earth, 5, incoming::UserObject { id: LegacyId(7), ..Default::default() }));
+    assert!(!intercept(&mut g_earth, 6, incoming::Users { users: vec![player(3, 1), player(7, 4)] }));
+
+    assert!(intercept(&mut g_earth, 1, chat(":ping")));
+    assert!(intercept(&mut g_earth, 1, chat(":add 1")));
+    let whispers: Vec<(HDirection, incoming::Whisper)> = g_earth.wait_for_sent_messages(2).unwrap().iter()
+        .map(| (direction, packet) | (direction.clone(), packet.clone().read()))
+        .collect();
+    assert_eq!(vec![
+        (HDirection::ToClient, incoming::Whisper { user_index: 4, text: String::from("pong"), ..Default::default() }),
+        (HDirection::ToClient, incoming::Whisper {
+            user_index: 4,
+            text: String::from("expected 2 argument(s), got 1, usage: :add <i32> <i32>"),
+            ..Default::default()
+        })
+    ], whispers);
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
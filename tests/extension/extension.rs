@@ -1,20 +1,72 @@
-use g_rust::extension::extension::{Extension};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use g_rust::extension::arguments::Argument;
+use g_rust::extension::extension::{Extension, PortSource};
 use g_rust::extension::parsers::{incoming, outgoing};
 use g_rust::extension::parsers::baseparser::BaseParser;
 use g_rust::extension::parsers::incoming::UserUpdate;
+use g_rust::extension::reconnect::{ConnectionKind, ReconnectPolicy};
 use g_rust::misc::connectioninfo::ConnectionInfo;
+use g_rust::misc::hclient::HClient;
 use g_rust::misc::hostinfo::HostInfo;
 use g_rust::protocol::hdirection::HDirection;
 use g_rust::protocol::hmessage::HMessage;
 use g_rust::protocol::hpacket::HPacket;
+use g_rust::protocol::vars::legacy::LegacyId;
+use g_rust::services::packetinfo::packetinfo::PacketInfo;
+use g_rust::services::packetinfo::packetinfomanager::PacketInfoManager;
+use g_rust::testing::MockGEarth;
+use g_rust::Error;
 
 #[derive(Debug, Default)]
 struct Test {}
 
+pub(super) fn packet_info(header_id: i32, name: &str, destination: HDirection) -> PacketInfo {
+    PacketInfo {
+        header_id,
+        name: name.to_string(),
+        destination,
+        ..PacketInfo::default()
+    }
+}
+
+pub(super) fn packet_info_manager() -> PacketInfoManager {
+    PacketInfoManager::new(vec![
+        packet_info(1, "Chat", HDirection::ToClient),
+        packet_info(2, "UserUpdate", HDirection::ToClient),
+        packet_info(1, "Chat", HDirection::ToServer)
+    ])
+}
+
+pub(super) fn connection_info() -> ConnectionInfo {
+    ConnectionInfo {
+        host: String::from("game-nl.habbo.com"),
+        port: 30000,
+        hotel_version: String::from("PRODUCTION-202301011200-000000000"),
+        client_identifier: String::from("HTML5"),
+        client: HClient::Flash
+    }
+}
+
+pub(super) fn chat(text: &str) -> incoming::Chat {
+    incoming::Chat {
+        user_index: 0,
+        text: text.to_string(),
+        gesture: 0,
+        style_id: 0,
+        links: vec![],
+        tracking_id: 2
+    }
+}
+
 #[test]
 fn test_connection() {
+    let mut g_earth = MockGEarth::bind().unwrap();
+    g_earth.flags = vec![String::from("-f")];
+
     let mut ext: Extension<Test> = Extension::new();
-    ext.args = vec!["-p".to_string(), "9092".to_string()];
+    ext.args = g_earth.args();
     ext.info.name = String::from("G-Rust test");
     ext.on_init(on_init);
     ext.on_socket_disconnect(on_socket_disconnect);
@@ -23,12 +75,31 @@ fn test_connection() {
     ext.on_click(on_click);
     ext.intercept_raw(incoming::Chat::get_direction(),"Chat", on_chat);
     ext.intercept(on_user_update);
-    ext.run();
+    let ext_thread = thread::spawn(move || ext.run());
+
+    g_earth.accept().unwrap();
+    assert_eq!("G-Rust test", g_earth.info_request().unwrap().name);
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+
+    g_earth.double_click().unwrap();
+    let (direction, packet) = g_earth.wait_for_sent_messages(1).unwrap()[0].clone();
+    assert_eq!(HDirection::ToClient, direction);
+    assert_eq!(chat("G-Rust says hi"), packet.clone().read());
+
+    let mut packet = HPacket::from_header_id(1);
+    packet.append(chat("hello"));
+    let mut msg = g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToClient, 0)).unwrap();
+    assert!(!msg.blocked);
+    assert_eq!(chat("G-Rust says hi"), msg.get_packet().read());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
 }
 
 fn on_init(ext: &mut Extension<Test>) {
     println!("Extension initialized");
-    ext.request_flags(on_flags);
+    ext.request_flags(on_flags).unwrap();
 }
 
 fn on_connect(ext: &mut Extension<Test>, info: ConnectionInfo) {
@@ -50,17 +121,10 @@ fn on_host_info_update(ext: &mut Extension<Test>, info: HostInfo) {
 }
 
 fn on_click(ext: &mut Extension<Test>) {
-    ext.send(incoming::Chat {
-        user_index: 0,
-        text: "G-Rust says hi".to_string(),
-        gesture: 0,
-        style_id: 0,
-        links: vec![],
-        tracking_id: 2
-    });
+    ext.send(chat("G-Rust says hi")).unwrap();
 }
 
-fn on_flags(ext: &mut Extension<Test>, flags: Vec<String>) {
+fn on_flags(_ext: &mut Extension<Test>, flags: Vec<String>) {
     println!("Flags: {flags:?}");
 }
 
@@ -72,17 +136,330 @@ fn on_chat(_ext: &mut Extension<Test>, msg: &mut HMessage) {
 
 #[test]
 fn test_intercept() {
+    let mut g_earth = MockGEarth::bind().unwrap();
     let mut ext: Extension<Test> = Extension::new();
-    ext.args = vec!["-p".to_string(), "9092".to_string()];
+    ext.args = g_earth.args();
     ext.info.name = String::from("G-Rust test");
     ext.intercept(on_user_update);
-    ext.run();
+    ext.intercept(| _ext: &mut Extension<Test>, msg: &mut HMessage, _chat: &mut outgoing::Chat | msg.blocked = true);
+    let ext_thread = thread::spawn(move || ext.run());
+
+    g_earth.accept().unwrap();
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+
+    let mut packet = HPacket::from_header_id(2);
+    packet.append(0);
+    let mut msg = g_earth.intercept(HMessage::from_packet_dir_index(packet.clone(), HDirection::ToClient, 0)).unwrap();
+    assert!(!msg.blocked);
+    assert_eq!(packet.get_bytes(), msg.get_packet().get_bytes());
+
+    let mut packet = HPacket::from_header_id(1);
+    packet.append((String::from("hi"), 0, 0));
+    assert!(g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToServer, 1)).unwrap().blocked);
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_channel_transport() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<Test> = Extension::new();
+    ext.info.name = String::from("G-Rust test");
+    ext.on_click(on_click);
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    assert_eq!("G-Rust test", g_earth.info_request().unwrap().name);
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    g_earth.double_click().unwrap();
+    assert_eq!(chat("G-Rust says hi"), g_earth.wait_for_sent_messages(1).unwrap()[0].1.clone().read());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_reconnect() {
+    let mut g_earth = MockGEarth::bind().unwrap();
+    let mut ext: Extension<Test> = Extension::new();
+    ext.args = g_earth.args();
+    ext.reconnect_policy = Some(ReconnectPolicy {
+        initial_delay: Duration::from_millis(10),
+        max_attempts: Some(2),
+        ..ReconnectPolicy::default()
+    });
+    let (sender, connections) = mpsc::channel();
+    ext.on_socket_connect(move | _ext, kind | sender.send(kind).unwrap());
+    ext.intercept(| _ext: &mut Extension<Test>, msg: &mut HMessage, _chat: &mut outgoing::Chat | msg.blocked = true);
+    let ext_thread = thread::spawn(move || ext.run());
+
+    g_earth.accept().unwrap();
+    g_earth.info_request().unwrap();
+    g_earth.disconnect();
+
+    g_earth.accept().unwrap();
+    g_earth.info_request().unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    let mut packet = HPacket::from_header_id(1);
+    packet.append((String::from("hi"), 0, 0));
+    assert!(g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToServer, 0)).unwrap().blocked);
+    assert_eq!(vec![ConnectionKind::New, ConnectionKind::Resumed], connections.try_iter().collect::<Vec<_>>());
+
+    drop(g_earth);
+    assert!(matches!(ext_thread.join().unwrap(), Err(Error::Connection(_))));
+}
+
+#[test]
+fn test_reconnect_config_error() {
+    let policy = ReconnectPolicy {
+        initial_delay: Duration::from_millis(10),
+        ..ReconnectPolicy::default()
+    };
+
+    let mut ext: Extension<Test> = Extension::new();
+    ext.port_source = PortSource::Env(String::from("G_RUST_TEST_UNSET_PORT"));
+    ext.reconnect_policy = Some(policy.clone());
+    assert!(matches!(ext.run(), Err(Error::Arguments(_))));
+
+    let mut g_earth = MockGEarth::bind().unwrap();
+    let mut ext: Extension<Test> = Extension::new();
+    ext.args = vec![];
+    ext.port_source = PortSource::Fixed(g_earth.port());
+    ext.add_argument(Argument::new("--room").required());
+    ext.reconnect_policy = Some(policy);
+    let ext_thread = thread::spawn(move || ext.run());
+
+    g_earth.accept().unwrap();
+    g_earth.set_timeout(Duration::from_millis(100));
+    assert!(g_earth.info_request().is_err());
+    assert!(matches!(ext_thread.join().unwrap(), Err(Error::Arguments(_))));
 }
 
-fn on_user_update(_ext: &mut Extension<Test>, msg: &mut HMessage, user_update: &mut UserUpdate) {
+#[test]
+fn test_reconnect_delay() {
+    let policy = ReconnectPolicy {
+        initial_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(5),
+        max_attempts: Some(4),
+        ..ReconnectPolicy::default()
+    };
+    let delays: Vec<Option<Duration>> = (0..5).map(| attempt | policy.delay(attempt)).collect();
+    assert_eq!(vec![Some(Duration::from_secs(1)), Some(Duration::from_secs(2)), Some(Duration::from_secs(4)), Some(Duration::from_secs(5)), None], delays);
+}
+
+fn on_user_update(_ext: &mut Extension<Test>, _msg: &mut HMessage, user_update: &mut UserUpdate) {
     println!("{user_update:?}");
 }
 
+#[test]
+fn test_closure_listeners() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<Test> = Extension::new();
+    let greeting = String::from("G-Rust says hi");
+    let mut clicks = 0;
+    ext.on_click(move | ext | {
+        clicks += 1;
+        ext.send(chat(&format!("{greeting} ({clicks})"))).unwrap();
+    });
+    let prefix = String::from(":");
+    ext.intercept(move | _ext: &mut Extension<Test>, msg: &mut HMessage, chat: &mut outgoing::Chat | {
+        if chat.text.starts_with(&prefix) {
+            msg.blocked = true;
+        }
+    });
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.info_request().unwrap();
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+
+    g_earth.double_click().unwrap();
+    g_earth.double_click().unwrap();
+    let sent: Vec<incoming::Chat> = g_earth.wait_for_sent_messages(2).unwrap().iter().cloned()
+        .map(| (_, mut packet) | packet.read())
+        .collect();
+    assert_eq!(vec![chat("G-Rust says hi (1)"), chat("G-Rust says hi (2)")], sent);
+
+    let mut packet = HPacket::from_header_id(1);
+    packet.append((String::from(":help"), 0, 0));
+    assert!(g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToServer, 0)).unwrap().blocked);
+    let mut packet = HPacket::from_header_id(1);
+    packet.append((String::from("help"), 0, 0));
+    assert!(!g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToServer, 1)).unwrap().blocked);
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_remove_intercept() {
+    let mut ext: Extension<Test> = Extension::new();
+    let first = ext.intercept(| _ext: &mut Extension<Test>, _msg: &mut HMessage, _chat: &mut outgoing::Chat | {});
+    let second = ext.intercept_raw_with_priority(HDirection::ToServer, "Chat", 10, | _ext, msg | msg.blocked = true);
+    assert_ne!(first, second);
+    assert!(ext.remove_intercept(first));
+    assert!(!ext.remove_intercept(first));
+    assert!(ext.remove_intercept(second));
+}
+
+#[test]
+fn test_intercept_order() {
+    let mut ext: Extension<Test> = Extension::new();
+    let (sender, calls) = mpsc::channel();
+    let listener = | name: &'static str | {
+        let sender = sender.clone();
+        move | _ext: &mut Extension<Test>, _msg: &mut HMessage | sender.send(name).unwrap()
+    };
+    ext.intercept_raw_with_priority(HDirection::ToClient, "Chat", 0, listener("name 0"));
+    let by_id = ext.intercept_raw_with_priority(HDirection::ToClient, 1, 0, listener("id 0"));
+    ext.intercept_raw_with_priority(HDirection::ToClient, "Chat", 10, listener("name 10"));
+    let late = listener("name 5");
+    ext.on_start(move | ext | {
+        assert!(ext.remove_intercept(by_id));
+        ext.intercept_raw_with_priority(HDirection::ToClient, "Chat", 5, late.clone());
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    intercept_chat(&mut g_earth, "hello");
+    assert_eq!(vec!["name 10", "name 5", "name 0"], calls.try_iter().collect::<Vec<_>>());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_handle_from_thread() {
+    let ext: Extension<Test> = Extension::new();
+    let handle = ext.handle();
+    let sender = thread::spawn(move || {
+        assert!(!handle.is_connected());
+        assert!(handle.get_packet_info_manager().is_none());
+        handle.send(outgoing::Chat {
+            text: "G-Rust says hi".to_string(),
+            chat_style: 0,
+            index: 0
+        })
+    });
+    assert!(matches!(sender.join().unwrap(), Err(Error::Disconnected)));
+}
+
+#[test]
+fn test_request_without_connection() {
+    let mut ext: Extension<Test> = Extension::new();
+    let request = outgoing::GetGuestRoom {
+        room_id: LegacyId(1),
+        entering_room: 0,
+        go_to_room: 0
+    };
+    let response = ext.request(
+        request.clone(),
+        | result: &incoming::GetGuestRoomResult | *result.data.flat_id == 1,
+        Duration::from_secs(1)
+    );
+    assert!(matches!(response, Err(Error::Disconnected)));
+
+    let response = ext.handle().request(
+        request,
+        | result: &incoming::GetGuestRoomResult | *result.data.flat_id == 1,
+        Duration::from_secs(1)
+    );
+    assert!(matches!(response, Err(Error::Disconnected)));
+}
+
+/// Runs `ext` over a channel against a mock G-Earth that has started a connection
+fn start(mut ext: Extension<Test>) -> (MockGEarth, thread::JoinHandle<g_rust::Result<()>>) {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    (g_earth, ext_thread)
+}
+
+fn intercept_chat(g_earth: &mut MockGEarth, text: &str) -> HMessage {
+    let mut packet = HPacket::from_header_id(1);
+    packet.append(chat(text));
+    g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToClient, 0)).unwrap()
+}
+
+#[test]
+fn test_send_unresolved_packet() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<Test> = Extension::new();
+    let (sender, results) = mpsc::channel();
+    ext.on_click(move | ext | {
+        sender.send(ext.send_to_server(HPacket::from_identifier_and_direction(String::from("Chat"), HDirection::ToServer))).unwrap();
+    });
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.double_click().unwrap();
+    let result = results.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(matches!(result, Err(Error::UnknownPacket { direction: HDirection::ToServer, name }) if name == "Chat"));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_request() {
+    let mut ext: Extension<Test> = Extension::new();
+    let (sender, responses) = mpsc::channel();
+    ext.on_click(move | ext | {
+        let response = ext.request(
+            outgoing::Chat {
+                text: String::from("ping"),
+                chat_style: 0,
+                index: 0
+            },
+            | chat: &incoming::Chat | chat.text == "pong",
+            Duration::from_secs(5)
+        );
+        sender.send(response).unwrap();
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    g_earth.double_click().unwrap();
+    let (direction, mut request) = g_earth.wait_for_sent_messages(1).unwrap()[0].clone();
+    assert_eq!(HDirection::ToServer, direction);
+    assert_eq!("ping", request.read::<outgoing::Chat>().text);
+
+    intercept_chat(&mut g_earth, "hello");
+    assert!(responses.try_recv().is_err());
+    intercept_chat(&mut g_earth, "pong");
+    assert_eq!(chat("pong"), responses.recv_timeout(Duration::from_secs(5)).unwrap().unwrap());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_request_timeout() {
+    let mut ext: Extension<Test> = Extension::new();
+    let (sender, responses) = mpsc::channel();
+    ext.on_click(move | ext | {
+        let response = ext.request(
+            outgoing::Chat::default(),
+            | chat: &incoming::Chat | chat.text == "pong",
+            Duration::from_millis(50)
+        );
+        sender.send(response).unwrap();
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    g_earth.double_click().unwrap();
+    g_earth.wait_for_sent_messages(1).unwrap();
+    assert!(matches!(responses.recv_timeout(Duration::from_secs(5)).unwrap(), Err(Error::Timeout)));
+
+    // Responses arriving after the timeout are left alone
+    assert!(!intercept_chat(&mut g_earth, "pong").blocked);
+    assert!(responses.try_recv().is_err());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
 #[test]
 fn test_hdirection_as_byte() {
     println!("{}", HDirection::ToClient as u8);
@@ -96,4 +473,91 @@ fn test_get_packet_name() {
     println!("{:?}", incoming::Chat::get_direction());
     println!("{}", outgoing::Chat::get_packet_name());
     println!("{:?}", outgoing::Chat::get_direction());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_conversion_without_connection() {
+    let mut ext: Extension<Test> = Extension::new();
+    let packet = HPacket::from_header_id(1);
+    assert!(matches!(ext.packet_to_string(&packet), Err(Error::Disconnected)));
+    assert!(matches!(ext.string_to_packet("{out:Chat}{s:\"hi\"}{i:0}{i:0}"), Err(Error::Disconnected)));
+    assert!(matches!(ext.handle().packet_to_expression(&packet), Err(Error::Disconnected)));
+}
+
+#[test]
+fn test_conversion_round_trip() {
+    let mut ext: Extension<Test> = Extension::new();
+    let (sender, results) = mpsc::channel();
+    ext.on_click(move | ext | {
+        let mut packet = HPacket::from_header_id(1);
+        packet.append((String::from("hi"), 0, 0));
+        let string = ext.packet_to_string(&packet).unwrap();
+        let expression = ext.packet_to_expression(&packet).unwrap();
+        let converted = ext.string_to_packet(&expression).unwrap();
+        let invalid = ext.string_to_packet("{h:");
+        sender.send((packet, string, converted, invalid)).unwrap();
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    g_earth.double_click().unwrap();
+    for count in 1..=4 {
+        g_earth.answer_conversions(count).unwrap();
+    }
+    let (packet, string, converted, invalid) = results.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!("[0][0][0][14][0][1][0][2]hi[0][0][0][0][0][0][0][0]", string);
+    assert_eq!(packet.bytes(), converted.bytes());
+    assert!(matches!(invalid, Err(Error::Encoding(_))));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_concurrent_conversions() {
+    let ext: Extension<Test> = Extension::new();
+    let handle = ext.handle();
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let threads: Vec<_> = (0..4)
+        .map(| i | {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                let mut packet = HPacket::from_header_id(i);
+                packet.append(i as i32);
+                (packet.clone(), handle.string_to_packet(&packet.to_expression()))
+            })
+        })
+        .collect();
+    // Every request is queued before the first response arrives, responses must go to the requests in order
+    g_earth.answer_conversions(4).unwrap();
+    for thread in threads {
+        let (packet, converted) = thread.join().unwrap();
+        assert_eq!(packet.bytes(), converted.unwrap().bytes());
+    }
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_malformed_message() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<Test> = Extension::new();
+    let (sender, host_infos) = mpsc::channel();
+    ext.on_host_info_update(move | _ext, host_info | sender.send(host_info).unwrap());
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    // Without host info the extension initializes without calling the host info listeners
+    // INIT
+    let mut init = HPacket::from_header_id(7);
+    init.append(false);
+    g_earth.send_raw(init).unwrap();
+    g_earth.wait_for_console_logs(1).unwrap();
+    assert!(host_infos.try_recv().is_err());
+
+    // CONNECTION_START missing everything after the host
+    let mut connection_start = HPacket::from_header_id(5);
+    connection_start.append(String::from("game-nl.habbo.com"));
+    g_earth.send_raw(connection_start).unwrap();
+    assert!(matches!(ext_thread.join().unwrap(), Err(Error::Decode(_))));
+}
@@ -0,0 +1,34 @@
+use std::thread;
+use log::{Level, LevelFilter, Log, Record};
+use g_rust::extension::extension::Extension;
+use g_rust::extension::logger::GEarthLogger;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::testing::MockGEarth;
+
+fn log(logger: &GEarthLogger, level: Level, text: &str) {
+    logger.log(&Record::builder().level(level).args(format_args!("{text}")).build());
+}
+
+#[test]
+fn logger() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<()> = Extension::new();
+    ext.info.name = String::from("G-Rust test");
+    let logger = GEarthLogger::new(ext.handle()).with_level(LevelFilter::Debug);
+    log(&logger, Level::Warn, "before init");
+    log(&logger, Level::Trace, "filtered");
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.init(false, HostInfo::default()).unwrap();
+    log(&logger, Level::Error, "after init");
+    log(&logger, Level::Debug, "debug");
+    assert_eq!(vec![
+        "[green] Extension \"G-Rust test\" successfully initialized",
+        "[orange] [G-Rust test] before init",
+        "[red] [G-Rust test] after init",
+        "[grey] [G-Rust test] debug"
+    ], g_earth.wait_for_console_logs(4).unwrap());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
@@ -1,2 +1,13 @@
 mod extension;
-mod parsers;
\ No newline at end of file
+mod parsers;
+mod asyncextension;
+mod command;
+mod logger;
+mod tracinglayer;
+mod arguments;
+mod builder;
+mod persist;
+mod capture;
+mod packetlog;
+mod sendqueue;
+mod scheduler;
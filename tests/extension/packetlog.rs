@@ -0,0 +1,66 @@
+use g_rust::extension::extension::Extension;
+use g_rust::extension::packetlog::PacketLog;
+use g_rust::extension::parsers::{incoming, outgoing};
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::Error;
+use super::extension::chat;
+
+const DUMP: &str = "\
+[Chat] <-- [2432][0][0][0][0][0][5]hello[0][0][0][0][0][0][0][0][0][0][0][0][0][0][0][2]
+{in:Chat}{i:0}{s:\"hello\"}{i:0}{i:0}{i:0}{i:2}
+--------------------
+[Chat] --> [1314][0][2]hi[0][0][0][0][0][0][0][0]
+<-- [99][0][0][0][5][91]x]
+
+{in:Chat}{i:0}{s:\"again\"}{i:0}{i:0}{i:0}{i:2}
+";
+
+fn body(packet: &mut HPacket) -> (i16, Vec<u8>) {
+    (packet.header_id(), packet.get_bytes()[6..].to_vec())
+}
+
+fn chat_body(text: &str) -> (i16, Vec<u8>) {
+    let mut packet = HPacket::from_header_id(2432);
+    packet.append(chat(text));
+    body(&mut packet)
+}
+
+#[test]
+fn test_parse_packet_log() {
+    let log: PacketLog = DUMP.parse().unwrap();
+    let messages: Vec<_> = log.messages.iter().cloned()
+        .map(| mut msg | (msg.get_index(), msg.get_destination(), body(msg.get_packet())))
+        .collect();
+    assert_eq!(vec![
+        (0, HDirection::ToClient, chat_body("hello")),
+        (1, HDirection::ToServer, (1314, vec![0, 2, b'h', b'i', 0, 0, 0, 0, 0, 0, 0, 0])),
+        (2, HDirection::ToClient, (99, vec![0, 0, 0, 5, b'[', b'x', b']'])),
+        (3, HDirection::ToClient, chat_body("again"))
+    ], messages);
+}
+
+#[test]
+fn test_replay_packet_log() {
+    let log: PacketLog = DUMP.parse().unwrap();
+    let mut ext: Extension<()> = Extension::new();
+    ext.intercept(| _ext: &mut Extension<()>, _msg: &mut HMessage, chat: &mut incoming::Chat | chat.text = chat.text.to_uppercase());
+    ext.intercept(| _ext: &mut Extension<()>, msg: &mut HMessage, _chat: &mut outgoing::Chat | msg.blocked = true);
+
+    let messages = log.replay(&mut ext);
+    assert_eq!(chat("HELLO"), messages[0].clone().get_packet().read());
+    assert!(messages[1].blocked);
+    assert!(!messages[2].blocked);
+    assert_eq!(chat("AGAIN"), messages[3].clone().get_packet().read());
+}
+
+#[test]
+fn test_invalid_packet_log() {
+    for (dump, line) in [("[Chat] [1][0]", 1), ("\n<-- [abc][0]", 2), ("{h:1}{i:0}", 1), ("{in:Unknown}{i:0}", 1)] {
+        match dump.parse::<PacketLog>() {
+            Err(Error::PacketLog(reason)) => assert!(reason.starts_with(&format!("line {line}: ")), "{reason}"),
+            res => panic!("expected an error for {dump:?}, got {res:?}")
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use g_rust::extension::parsers::baseparser::BaseParser;
+use g_rust::extension::parsers::incoming::{Chat, GetGuestRoomResult};
+use g_rust::protocol::hpacket::HPacket;
+
+#[test]
+fn try_parse_names_field() {
+    let mut packet = HPacket::from_header_id(0);
+    packet.append(3);
+    let e = Chat::try_parse(&mut packet).unwrap_err();
+    assert_eq!("text", e.field_path());
+    assert_eq!(4, e.offset);
+}
+
+#[test]
+fn try_parse_names_manual_field() {
+    let mut packet = HPacket::from_header_id(0);
+    packet.append((true, 1, "room".to_string(), 2));
+    let e = GetGuestRoomResult::try_parse(&mut packet).unwrap_err();
+    assert_eq!("data.owner_name", e.field_path());
+}
@@ -1 +1,2 @@
-mod outgoing;
\ No newline at end of file
+mod outgoing;
+mod incoming;
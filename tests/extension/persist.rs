@@ -0,0 +1,83 @@
+#![cfg(feature = "persist")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use serde::{Deserialize, Serialize};
+use g_rust::extension::extension::Extension;
+use g_rust::extension::persist;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::testing::MockGEarth;
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Globals {
+    prefix: String,
+    connections: u32
+}
+
+fn state_path(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("g-rust-persist-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join(persist::STATE_FILE)
+}
+
+#[test]
+fn test_persist() {
+    let path = state_path("persist");
+
+    let mut ext: Extension<Globals> = Extension::new();
+    ext.globals.prefix = String::from(":");
+    ext.persist_to(&path);
+    assert_eq!(":", ext.globals.prefix);
+    ext.save_state().unwrap();
+
+    let mut ext: Extension<Globals> = Extension::new();
+    ext.persist_to(&path);
+    assert_eq!(Globals { prefix: String::from(":"), connections: 0 }, ext.globals);
+}
+
+#[test]
+fn test_persist_on_disconnect() {
+    let path = state_path("disconnect");
+    let (g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<Globals> = Extension::new();
+    ext.persist_to(&path);
+    ext.on_socket_connect(| ext, _kind | ext.globals.connections += 1);
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+
+    let mut ext: Extension<Globals> = Extension::new();
+    ext.persist_to(&path);
+    assert_eq!(1, ext.globals.connections);
+}
+
+#[test]
+fn test_persist_schema_mismatch() {
+    let path = state_path("mismatch");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, r#"{"prefix": 5}"#).unwrap();
+
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<Globals> = Extension::new();
+    ext.info.name = String::from("G-Rust test");
+    ext.globals.connections = 3;
+    ext.persist_to(&path);
+    assert_eq!(Globals::default(), ext.globals);
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.init(false, HostInfo::default()).unwrap();
+    let logs = g_earth.wait_for_console_logs(2).unwrap();
+    assert!(logs[1].starts_with("[orange] [G-Rust test] couldn't persist state: "));
+    assert!(logs[1].ends_with(", using default globals"));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_state_path() {
+    let path = persist::state_path("G-Rust: test/1").unwrap();
+    assert!(path.ends_with("g-rust/G-Rust_ test_1/state.json"));
+}
@@ -0,0 +1,140 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use g_rust::extension::extension::Extension;
+use g_rust::extension::parsers::incoming;
+use g_rust::extension::scheduler::TaskHandle;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::testing::MockGEarth;
+use super::extension::{chat, connection_info, packet_info_manager};
+
+fn sent_texts(g_earth: &mut MockGEarth, count: usize) -> Vec<String> {
+    g_earth.wait_for_sent_messages(count).unwrap().iter()
+        .map(| (_, packet) | packet.clone().read::<incoming::Chat>().text)
+        .collect()
+}
+
+/// Runs `ext` against a mock G-Earth that has started a connection
+fn start(mut ext: Extension<()>) -> (MockGEarth, thread::JoinHandle<g_rust::Result<()>>) {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    (g_earth, ext_thread)
+}
+
+/// Schedules a task sending `text` in `delay` once the connection started and passes its handle on
+fn schedule_on_start(ext: &mut Extension<()>, delay: Duration, text: &'static str) -> mpsc::Receiver<TaskHandle> {
+    let (sender, handles) = mpsc::channel();
+    ext.on_start(move | ext | {
+        let handle = ext.schedule_once(delay, move | ext | ext.send(chat(text)).unwrap());
+        sender.send(handle).unwrap();
+    });
+    handles
+}
+
+#[test]
+fn test_schedule() {
+    let mut ext: Extension<()> = Extension::new();
+    let once = ext.schedule_once(Duration::from_millis(500), | ext | {
+        ext.write_to_console(String::from("500 ms passed")).unwrap();
+    });
+    let repeating = ext.schedule_repeating(Duration::from_secs(30), | ext | {
+        ext.write_to_console(String::from("30 s passed")).unwrap();
+    });
+    assert!(once.is_active());
+    assert!(repeating.is_active());
+
+    repeating.cancel();
+    assert!(once.is_active());
+    assert!(!repeating.is_active());
+}
+
+#[test]
+fn test_schedule_once_fires() {
+    let mut ext: Extension<()> = Extension::new();
+    let handles = schedule_on_start(&mut ext, Duration::from_millis(20), "once");
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let handle = handles.recv().unwrap();
+    assert_eq!(vec!["once"], sent_texts(&mut g_earth, 1));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+    assert!(!handle.is_active());
+}
+
+#[test]
+fn test_schedule_repeating_fires_again() {
+    let mut ext: Extension<()> = Extension::new();
+    let (sender, handles) = mpsc::channel();
+    ext.on_start(move | ext | {
+        let mut count = 0;
+        let handle = ext.schedule_repeating(Duration::from_millis(10), move | ext | {
+            count += 1;
+            ext.send(chat(&count.to_string())).unwrap();
+        });
+        sender.send(handle).unwrap();
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let handle = handles.recv().unwrap();
+    assert_eq!(vec!["1", "2", "3"], sent_texts(&mut g_earth, 3));
+    assert!(handle.is_active());
+    handle.cancel();
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_cancel_scheduled_task() {
+    let mut ext: Extension<()> = Extension::new();
+    let cancelled = schedule_on_start(&mut ext, Duration::from_millis(200), "cancelled");
+    let sentinel = schedule_on_start(&mut ext, Duration::from_millis(400), "sentinel");
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let handle = cancelled.recv().unwrap();
+    handle.cancel();
+    assert!(!handle.is_active());
+    assert_eq!(vec!["sentinel"], sent_texts(&mut g_earth, 1));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+    assert!(!sentinel.recv().unwrap().is_active());
+}
+
+#[test]
+fn test_tasks_cancelled_on_connection_end() {
+    let mut ext: Extension<()> = Extension::new();
+    let handles = schedule_on_start(&mut ext, Duration::from_secs(30), "never");
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let handle = handles.recv().unwrap();
+    assert!(handle.is_active());
+    g_earth.connection_end().unwrap();
+    // The extension answers intercepts in order, so the connection end has been handled once this returns
+    let mut packet = HPacket::from_header_id(1);
+    packet.append(chat("hello"));
+    g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToClient, 0)).unwrap();
+    assert!(!handle.is_active());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_tasks_cancelled_on_disconnect() {
+    let mut ext: Extension<()> = Extension::new();
+    let handles = schedule_on_start(&mut ext, Duration::from_secs(30), "never");
+    let (g_earth, ext_thread) = start(ext);
+
+    let handle = handles.recv().unwrap();
+    assert!(handle.is_active());
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+    assert!(!handle.is_active());
+}
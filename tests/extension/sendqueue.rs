@@ -0,0 +1,180 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use g_rust::extension::extension::Extension;
+use g_rust::extension::parsers::{incoming, outgoing};
+use g_rust::extension::sendqueue::RateLimit;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::services::packetinfo::packetinfomanager::PacketInfoManager;
+use g_rust::testing::MockGEarth;
+use g_rust::Error;
+use super::extension::{connection_info, packet_info};
+
+fn packet_info_manager() -> PacketInfoManager {
+    PacketInfoManager::new(vec![
+        packet_info(1, "Chat", HDirection::ToServer),
+        packet_info(2, "FloodControl", HDirection::ToClient)
+    ])
+}
+
+fn chat(text: &str) -> outgoing::Chat {
+    outgoing::Chat {
+        text: text.to_string(),
+        ..outgoing::Chat::default()
+    }
+}
+
+fn sent_texts(g_earth: &mut MockGEarth, count: usize) -> Vec<String> {
+    g_earth.wait_for_sent_messages(count).unwrap().iter()
+        .map(| (_, packet) | packet.clone().read::<outgoing::Chat>().text)
+        .collect()
+}
+
+/// Runs `ext` against a mock G-Earth that has started a connection
+fn start(mut ext: Extension<()>) -> (MockGEarth, thread::JoinHandle<g_rust::Result<()>>) {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+    g_earth.init(false, HostInfo::default()).unwrap();
+    g_earth.connection_start(connection_info(), packet_info_manager()).unwrap();
+    (g_earth, ext_thread)
+}
+
+#[test]
+fn test_rate_limit() {
+    let mut ext: Extension<()> = Extension::new();
+    ext.set_rate_limit("Chat", RateLimit::new(2, Duration::from_millis(200)));
+    ext.on_start(| ext | {
+        for text in ["a", "b", "c"] {
+            ext.queue(chat(text));
+        }
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let start = Instant::now();
+    assert_eq!(vec!["a", "b"], sent_texts(&mut g_earth, 2));
+    assert!(start.elapsed() < Duration::from_millis(150));
+    assert_eq!(vec!["a", "b", "c"], sent_texts(&mut g_earth, 3));
+    assert!(start.elapsed() >= Duration::from_millis(150));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_priority_and_callbacks() {
+    let (sender, results) = mpsc::channel();
+    let mut ext: Extension<()> = Extension::new();
+    ext.set_rate_limit("Chat", RateLimit::new(1, Duration::from_millis(20)));
+    ext.on_start(move | ext | {
+        for (priority, text) in [(0, "low"), (5, "high"), (1, "medium"), (0, "cancelled")] {
+            let sender = sender.clone();
+            let handle = ext.queue_with_priority(priority, chat(text), move | _ext, res | sender.send((text, res.is_ok())).unwrap());
+            if text == "cancelled" {
+                handle.cancel();
+            }
+        }
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert_eq!(vec!["high", "medium", "low"], sent_texts(&mut g_earth, 3));
+    assert_eq!(vec![("cancelled", false), ("high", true), ("medium", true), ("low", true)], results.iter().take(4).collect::<Vec<_>>());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+    assert!(results.try_recv().is_err());
+}
+
+#[test]
+fn test_cancel_queued() {
+    let (sender, results) = mpsc::channel();
+    let (handle_sender, handles) = mpsc::channel();
+    let mut ext: Extension<()> = Extension::new();
+    ext.set_rate_limit("Chat", RateLimit::new(1, Duration::from_secs(60)));
+    ext.on_start(move | ext | {
+        for text in ["sent", "cancelled"] {
+            let sender = sender.clone();
+            let handle = ext.queue_with_priority(0, chat(text), move | _ext, res | sender.send((text, res.map_err(| e | matches!(e, Error::Cancelled)))).unwrap());
+            handle_sender.send(handle).unwrap();
+        }
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert_eq!(vec!["sent"], sent_texts(&mut g_earth, 1));
+    assert_eq!(("sent", Ok(())), results.recv_timeout(Duration::from_secs(5)).unwrap());
+    let cancelled = handles.iter().nth(1).unwrap();
+    assert!(cancelled.is_active());
+    cancelled.cancel();
+    // Wakes up the extension, which otherwise waits for the rate limit
+    g_earth.double_click().unwrap();
+    assert_eq!(("cancelled", Err(true)), results.recv_timeout(Duration::from_secs(5)).unwrap());
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+    assert!(results.try_recv().is_err());
+}
+
+#[test]
+fn test_flood_control() {
+    let mut ext: Extension<()> = Extension::new();
+    ext.remove_rate_limit("Chat");
+    ext.intercept(| ext: &mut Extension<()>, _msg: &mut HMessage, _flood_control: &mut incoming::FloodControl | {
+        ext.queue(chat("after flood control"));
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    let mut packet = HPacket::from_header_id(2);
+    packet.append(incoming::FloodControl { seconds: 1 });
+    let start = Instant::now();
+    g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToClient, 0)).unwrap();
+    assert_eq!(vec!["after flood control"], sent_texts(&mut g_earth, 1));
+    assert!(start.elapsed() >= Duration::from_millis(900));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_flood_control_after_first_queue() {
+    let mut ext: Extension<()> = Extension::new();
+    ext.on_start(| ext | {
+        ext.queue(chat("before flood control"));
+    });
+    ext.on_click(| ext | {
+        ext.queue(chat("after flood control"));
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert_eq!(vec!["before flood control"], sent_texts(&mut g_earth, 1));
+    let mut packet = HPacket::from_header_id(2);
+    packet.append(incoming::FloodControl { seconds: 1 });
+    let start = Instant::now();
+    g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToClient, 0)).unwrap();
+    g_earth.double_click().unwrap();
+    assert_eq!(vec!["before flood control", "after flood control"], sent_texts(&mut g_earth, 2));
+    assert!(start.elapsed() >= Duration::from_millis(900));
+
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_queue_dropped_on_disconnect() {
+    let (sender, results) = mpsc::channel();
+    let mut ext: Extension<()> = Extension::new();
+    ext.set_rate_limit("Chat", RateLimit::new(1, Duration::from_secs(60)));
+    ext.on_start(move | ext | {
+        for text in ["sent", "dropped"] {
+            let sender = sender.clone();
+            ext.queue_with_priority(0, chat(text), move | ext, res | sender.send((res.map_err(| e | matches!(e, Error::Disconnected)), ext.queued_packets())).unwrap());
+        }
+    });
+    let (mut g_earth, ext_thread) = start(ext);
+
+    assert_eq!(vec!["sent"], sent_texts(&mut g_earth, 1));
+    drop(g_earth);
+    ext_thread.join().unwrap().unwrap();
+    assert_eq!(vec![(Ok(()), 1), (Err(true), 0)], results.try_iter().collect::<Vec<_>>());
+}
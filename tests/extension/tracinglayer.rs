@@ -0,0 +1,59 @@
+#![cfg(feature = "tracing")]
+
+use std::thread;
+use std::time::Duration;
+use tracing_subscriber::prelude::*;
+use g_rust::extension::extension::Extension;
+use g_rust::extension::parsers::outgoing;
+use g_rust::extension::tracinglayer::GEarthLayer;
+use g_rust::misc::connectioninfo::ConnectionInfo;
+use g_rust::misc::hclient::HClient;
+use g_rust::misc::hostinfo::HostInfo;
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hmessage::HMessage;
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::services::packetinfo::packetinfo::PacketInfo;
+use g_rust::services::packetinfo::packetinfomanager::PacketInfoManager;
+use g_rust::testing::MockGEarth;
+
+#[test]
+fn tracing_layer() {
+    let (mut g_earth, transport) = MockGEarth::channel();
+    let mut ext: Extension<()> = Extension::new();
+    ext.info.name = String::from("G-Rust test");
+    ext.intercept(| _ext: &mut Extension<()>, _msg: &mut HMessage, chat: &mut outgoing::Chat | {
+        tracing::info!(style = chat.chat_style, "said {}", chat.text);
+        tracing::debug!("filtered");
+    });
+    let layer = GEarthLayer::new(ext.handle()).with_slow_span_threshold(Some(Duration::ZERO));
+
+    let g_earth_thread = thread::spawn(move || {
+        g_earth.init(false, HostInfo::default()).unwrap();
+        g_earth.connection_start(ConnectionInfo {
+            host: String::new(),
+            port: 0,
+            hotel_version: String::new(),
+            client_identifier: String::new(),
+            client: HClient::Flash
+        }, PacketInfoManager::new(vec![PacketInfo {
+            header_id: 1,
+            name: String::from("Chat"),
+            destination: HDirection::ToServer,
+            ..PacketInfo::default()
+        }])).unwrap();
+
+        let mut packet = HPacket::from_header_id(1);
+        packet.append((String::from("hi"), 3, 0));
+        g_earth.intercept(HMessage::from_packet_dir_index(packet, HDirection::ToServer, 5)).unwrap();
+        g_earth.wait_for_console_logs(3).unwrap().to_vec()
+    });
+
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        ext.run_with_transport(transport).unwrap();
+    });
+
+    let logs = g_earth_thread.join().unwrap();
+    let span = "intercept{direction=TOSERVER index=5 header_id=1 packet=\"Chat\"}";
+    assert_eq!(format!("[white] [G-Rust test] {span}: said hi style=3"), logs[1]);
+    assert!(logs[2].starts_with(&format!("[orange] [G-Rust test] {span} took ")), "{}", logs[2]);
+}
@@ -0,0 +1,40 @@
+use std::io::{ErrorKind, Read, Write};
+use std::thread;
+use std::time::Duration;
+use g_rust::extension::extension::Extension;
+use g_rust::misc::transport::ChannelTransport;
+use g_rust::protocol::hpacket::HPacket;
+
+#[test]
+fn channel_transport() {
+    let (mut a, mut b) = ChannelTransport::pair();
+    a.write_all(&[1, 2, 3]).unwrap();
+    a.write_all(&[4]).unwrap();
+
+    let mut bytes = [0u8; 4];
+    b.read_exact(&mut bytes).unwrap();
+    assert_eq!([1, 2, 3, 4], bytes);
+
+    b.set_read_timeout(Some(Duration::from_millis(10)));
+    assert_eq!(ErrorKind::TimedOut, b.read(&mut bytes).unwrap_err().kind());
+
+    drop(a);
+    assert_eq!(0, b.read(&mut bytes).unwrap());
+    assert!(b.write_all(&[1]).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_transport() {
+    let (mut g_earth, transport) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut ext: Extension<()> = Extension::new();
+    let ext_thread = thread::spawn(move || ext.run_with_transport(transport));
+
+    g_earth.write_all(&HPacket::from_header_id(2).get_bytes()).unwrap();
+    let mut response = [0u8; 6];
+    g_earth.read_exact(&mut response).unwrap();
+    assert_eq!(1, HPacket::from_bytes(response.to_vec()).header_id());
+
+    g_earth.shutdown(std::net::Shutdown::Both).unwrap();
+    ext_thread.join().unwrap().unwrap();
+}
@@ -0,0 +1,47 @@
+use g_rust::error::Error;
+use g_rust::protocol::dynamicpacket::{DynamicPacket, DynType, DynValue};
+use g_rust::protocol::hpacket::HPacket;
+
+#[test]
+fn parse_structure() {
+    assert_eq!(vec![DynType::Int, DynType::String, DynType::LongString, DynType::Byte], DynType::parse_structure("i, s,SB").unwrap());
+    assert!(DynType::parse_structure("ix").is_err());
+}
+
+#[test]
+fn read_and_write_back() {
+    let mut packet = HPacket::from_header_id(1234);
+    packet.append((5, String::from("hi"), true, 7u8, 3i64));
+    packet.append_bytes(vec![1, 2]);
+
+    let mut dynamic = DynamicPacket::read(&packet, "isb").unwrap();
+    assert_eq!(vec![DynValue::Int(5), DynValue::String(String::from("hi")), DynValue::Bool(true)], dynamic.values);
+    assert_eq!(vec![7, 0, 0, 0, 0, 0, 0, 0, 3, 1, 2], dynamic.rest);
+    assert_eq!(packet.get_bytes(), dynamic.to_packet().get_bytes());
+
+    dynamic.values[1] = DynValue::String(String::from("hello"));
+    let mut edited = dynamic.to_packet();
+    assert_eq!(1234, edited.header_id());
+    assert_eq!((5, String::from("hello"), true, 7u8, 3i64), edited.read());
+}
+
+#[test]
+fn read_error() {
+    let mut packet = HPacket::from_header_id(1);
+    packet.append(5);
+
+    match DynamicPacket::read(&packet, "ii") {
+        Err(Error::Decode(e)) => assert_eq!("[1]", e.field_path()),
+        res => panic!("expected a decode error, got {res:?}")
+    }
+}
+
+#[test]
+fn dump() {
+    let mut packet = HPacket::from_header_id(1);
+    packet.append((5, String::from("hi")));
+    packet.append(9u8);
+
+    let dump = DynamicPacket::read(&packet, "is").unwrap().to_string();
+    assert_eq!("Unknown (?, header 1, structure \"is\")\n  [0] i @0: 5\n  [1] s @4: \"hi\"\n  1 unread bytes @8: [9]\n", dump);
+}
@@ -0,0 +1,43 @@
+use g_rust::protocol::hdirection::HDirection;
+use g_rust::protocol::hpacket::HPacket;
+
+#[test]
+fn from_expression() {
+    let mut packet = HPacket::from_expression("{h:1234}{i:5}{s:\"a \\\"b\\\"\"}{b:true}{b:-1}{u:2}{l:3}").unwrap();
+    assert_eq!(1234, packet.header_id());
+    let res: (i32, String, bool, u8, u16, i64) = packet.read();
+    assert_eq!((5, String::from("a \"b\""), true, 255, 2, 3), res);
+    assert_eq!(0, packet.get_bytes_available());
+}
+
+#[test]
+fn from_expression_identifier() {
+    let mut packet = HPacket::from_expression("{out:Chat} {s:\"hi\"} {i:0} {i:0}").unwrap();
+    assert_eq!("Chat", packet.identifier);
+    assert_eq!(HDirection::ToServer, packet.identifier_direction);
+    assert_eq!((String::from("hi"), 0, 0), packet.read::<(String, i32, i32)>());
+}
+
+#[test]
+fn from_invalid_expression() {
+    assert!(HPacket::from_expression("{i:5}").is_err());
+    assert!(HPacket::from_expression("{h:1}{i:abc}").is_err());
+    assert!(HPacket::from_expression("{h:1}{s:\"unterminated}").is_err());
+    assert!(HPacket::from_expression("{h:1}{x:1}").is_err());
+    assert!(HPacket::from_expression("{h:1}{i:1}{h:2}").is_err());
+}
+
+#[test]
+fn to_expression() {
+    let mut packet = HPacket::from_header_id(1234);
+    packet.append((7i32, String::from("line\n\"quoted\""), true, 1.5f64));
+    let expression = packet.to_expression_with_structure("i,s,b,d").unwrap();
+    assert_eq!("{h:1234}{i:7}{s:\"line\\n\\\"quoted\\\"\"}{b:true}{d:1.5}", expression);
+    assert_eq!(packet.get_bytes(), HPacket::from_expression(&expression).unwrap().get_bytes());
+
+    let raw = packet.to_expression();
+    assert!(raw.starts_with("{h:1234}{b:0}{b:0}{b:0}{b:7}"));
+    assert_eq!(packet.get_bytes(), HPacket::from_expression(&raw).unwrap().get_bytes());
+
+    assert!(packet.to_expression_with_structure("l,l,l,l").is_err());
+}
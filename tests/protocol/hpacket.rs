@@ -87,4 +87,55 @@ fn stringify() {
     println!("{}", packet.stringify());
     let mut packet2 = HPacket::from_string(packet.stringify());
     println!("{:?}", packet2.get_bytes());
-}
\ No newline at end of file
+}
+
+#[test]
+fn try_read() {
+    let mut packet = HPacket::from_bytes(vec![0, 0, 0, 7, 0, 152, 0, 5, b'a', b'b', b'c']);
+    assert_eq!(0, packet.try_read::<String>().unwrap_err().offset);
+    assert_eq!(6, packet.read_index);
+
+    let length: u16 = packet.try_read().unwrap();
+    assert_eq!(5, length);
+    assert_eq!(8, packet.read_index);
+    assert_eq!(2, packet.try_read::<i64>().unwrap_err().offset);
+
+    packet.read_index = 20;
+    assert!(packet.try_read::<u8>().is_err());
+}
+
+#[test]
+fn try_read_error_path() {
+    let mut packet = HPacket::from_header_id_and_bytes(152, vec![
+        0, 0, 0, 2,
+        0, 0, 0, 1, 0, 1, b'a',
+        0, 0, 0, 2, 0, 5, b'a'
+    ]);
+    let e = packet.try_read::<Vec<(i32, String)>>().unwrap_err();
+    assert_eq!("[1].1", e.field_path());
+    assert_eq!(15, e.offset);
+    assert_eq!(6, packet.read_index);
+}
+
+#[test]
+fn replace() {
+    let mut packet = HPacket::from_header_id(152);
+    packet.append((1i32, String::from("a"), 2i32));
+    packet.replace(10, String::from("abc"));
+    packet.read_index = 6;
+    assert_eq!((1, String::from("abc"), 2), packet.read::<(i32, String, i32)>());
+    assert_eq!(15, packet.length());
+}
+
+#[test]
+fn borrowed_bytes() {
+    let mut packet = HPacket::from_header_id(152);
+    packet.append(vec![(1i32, String::from("a"))]);
+    let body = vec![0, 0, 0, 1, 0, 0, 0, 1, 0, 1, b'a'];
+    assert_eq!(packet.get_bytes(), packet.bytes());
+    assert_eq!(&body[..], &packet.bytes()[6..]);
+
+    assert_eq!(&body[..4], packet.read_slice(4));
+    assert_eq!(10, packet.read_index);
+    assert_eq!(body, packet.into_body());
+}
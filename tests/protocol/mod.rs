@@ -1 +1,4 @@
-pub mod hpacket;
\ No newline at end of file
+pub mod hpacket;
+pub mod packetreader;
+pub mod expression;
+pub mod dynamicpacket;
\ No newline at end of file
@@ -0,0 +1,35 @@
+use g_rust::protocol::hpacket::HPacket;
+use g_rust::protocol::packetreader::PacketReader;
+
+#[test]
+fn read() {
+    let bytes = [0, 0, 0, 7, 0, 3, b'a', b'b', b'c', 1];
+    let mut reader = PacketReader::new(&bytes);
+    let (number, s): (i32, String) = reader.read();
+    assert_eq!(7, number);
+    assert_eq!("abc", s);
+    assert_eq!(9, reader.position());
+    assert!(reader.read::<bool>());
+    assert!(reader.remaining().is_empty());
+}
+
+#[test]
+fn try_read() {
+    let bytes = [0, 0, 0, 7, 0, 5, b'a'];
+    let mut reader = PacketReader::new(&bytes);
+    assert_eq!(7, reader.try_read::<i32>().unwrap());
+
+    let e = reader.try_read_field::<String>("name").unwrap_err();
+    assert_eq!("name", e.field_path());
+    assert_eq!(4, e.offset);
+    assert_eq!(4, reader.position());
+}
+
+#[test]
+fn packet_reader() {
+    let mut packet = HPacket::from_header_id_and_bytes(152, vec![0, 0, 0, 1, 0, 0, 0, 2]);
+    packet.read::<i32>();
+    let mut reader = packet.reader();
+    assert_eq!(2, reader.read::<i32>());
+    assert_eq!(10, packet.read_index);
+}
@@ -2,4 +2,7 @@
 mod protocol;
 
 #[cfg(test)]
-mod extension;
\ No newline at end of file
+mod extension;
+
+#[cfg(test)]
+mod misc;